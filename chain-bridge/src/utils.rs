@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use codec::{Encode, Decode};
+use codec::{Encode, Decode, Error as CodecError, Input, Output};
 
 #[derive(Encode, Decode, PartialEq, Clone, Debug)]
 pub enum AssetType {
@@ -24,18 +24,137 @@ pub enum AssetType {
     Runes,
 }
 
-#[derive(Encode, Decode, PartialEq, Clone, Debug)]
+/// Leading byte of a `BtcTxMessage` encoding that carries sighash types. Legacy messages
+/// start with the compact length of `txs`, and `0xff` is never a valid compact `u32` prefix.
+pub const BTC_TX_MESSAGE_VERSIONED: u8 = 0xff;
+/// Version following [`BTC_TX_MESSAGE_VERSIONED`], `(txs, asset, taproot_data)` then the
+/// per-input sighash types of every tx.
+pub const BTC_TX_MESSAGE_VERSION: u8 = 1;
+
+/// Encoded as `(txs, asset, taproot_data)` when no tx sets sighash types, so messages from
+/// before sighash types decode as is, and as the versioned encoding otherwise.
+#[derive(PartialEq, Clone, Debug)]
 pub struct BtcTxMessage {
     pub txs: Vec<BtcSingleTx>,
     pub asset: AssetType,
     pub taproot_data: Option<Vec<u8>>,
 }
 
+impl Encode for BtcTxMessage {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        let typed = self.txs.iter().any(|tx| !tx.sighash_types.is_empty());
+        if typed {
+            dest.push_byte(BTC_TX_MESSAGE_VERSIONED);
+            dest.push_byte(BTC_TX_MESSAGE_VERSION);
+        }
+        self.txs.encode_to(dest);
+        self.asset.encode_to(dest);
+        self.taproot_data.encode_to(dest);
+        if typed {
+            let sighash_types: Vec<&Vec<BtcSighashType>> = self.txs.iter().map(|tx| &tx.sighash_types).collect();
+            sighash_types.encode_to(dest);
+        }
+    }
+}
+
+impl Decode for BtcTxMessage {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let first = input.read_byte()?;
+        if first != BTC_TX_MESSAGE_VERSIONED {
+            let txs = Vec::<BtcSingleTx>::decode(&mut Unread { byte: Some(first), input: &mut *input })?;
+            let asset = AssetType::decode(input)?;
+            let taproot_data = Option::<Vec<u8>>::decode(input)?;
+            return Ok(BtcTxMessage { txs, asset, taproot_data });
+        }
+        if input.read_byte()? != BTC_TX_MESSAGE_VERSION {
+            return Err("unsupported BtcTxMessage version".into());
+        }
+        let mut txs = Vec::<BtcSingleTx>::decode(input)?;
+        let asset = AssetType::decode(input)?;
+        let taproot_data = Option::<Vec<u8>>::decode(input)?;
+        let sighash_types = Vec::<Vec<BtcSighashType>>::decode(input)?;
+        if sighash_types.len() != txs.len() {
+            return Err("BtcTxMessage sighash types not match txs".into());
+        }
+        for (tx, sighash_types) in txs.iter_mut().zip(sighash_types) {
+            tx.sighash_types = sighash_types;
+        }
+        Ok(BtcTxMessage { txs, asset, taproot_data })
+    }
+}
+
+/// `input` with one already read byte put back in front of it.
+struct Unread<'a, I> {
+    byte: Option<u8>,
+    input: &'a mut I,
+}
+
+impl<I: Input> Input for Unread<'_, I> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, CodecError> {
+        Ok(self.input.remaining_len()?.map(|len| len + self.byte.is_some() as usize))
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), CodecError> {
+        match self.byte {
+            Some(byte) if !into.is_empty() => {
+                self.byte = None;
+                into[0] = byte;
+                self.input.read(&mut into[1..])
+            }
+            _ => self.input.read(into),
+        }
+    }
+}
+
 #[derive(Encode, Decode, PartialEq, Clone, Debug)]
 pub struct BtcSingleTx {
     pub raw_hex: String,
     pub hash_to_sign: Vec<String>,
     pub input_values: Vec<u64>,
+    /// Sighash type of each input, empty when every input uses the default (`SIGHASH_ALL`
+    /// for ecdsa, `SIGHASH_DEFAULT` for schnorr). Carried by the versioned `BtcTxMessage`
+    /// encoding.
+    #[codec(skip)]
+    pub sighash_types: Vec<BtcSighashType>,
+}
+
+impl BtcSingleTx {
+    /// Sighash type the message declares for input `index`, `None` for the default.
+    pub fn sighash_type(&self, index: usize) -> Option<BtcSighashType> {
+        self.sighash_types.get(index).copied()
+    }
+}
+
+/// Bitcoin signature hash type, the byte appended to a signature in scriptSig/witness.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BtcSighashType {
+    /// Taproot only, a 64-byte schnorr signature without the trailing byte.
+    Default = 0x00,
+    All = 0x01,
+    None = 0x02,
+    Single = 0x03,
+    AllAnyoneCanPay = 0x81,
+    NoneAnyoneCanPay = 0x82,
+    SingleAnyoneCanPay = 0x83,
+}
+
+impl BtcSighashType {
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(BtcSighashType::Default),
+            0x01 => Some(BtcSighashType::All),
+            0x02 => Some(BtcSighashType::None),
+            0x03 => Some(BtcSighashType::Single),
+            0x81 => Some(BtcSighashType::AllAnyoneCanPay),
+            0x82 => Some(BtcSighashType::NoneAnyoneCanPay),
+            0x83 => Some(BtcSighashType::SingleAnyoneCanPay),
+            _ => None,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
 }
 
 pub fn disintegrate_btc_msg(
@@ -87,52 +206,53 @@ pub fn disintegrate_btc_msg(
             }
         }
     }
+    for tx in &btc_msg.txs {
+        if !tx.sighash_types.is_empty() && tx.sighash_types.len() != tx.hash_to_sign.len() {
+            return Err(
+                format!(
+                    "btc msg invalid sighash_types num {} with hash_to_sign num {}",
+                    tx.sighash_types.len(),
+                    tx.hash_to_sign.len()
+                )
+            );
+        }
+    }
     Ok(btc_msg)
 }
 
-fn disintegrate_btc_signatures(raw_sig: Vec<u8>, is_ecdsa: bool) -> Option<Vec<Vec<u8>>> {
-    let sig_len = if is_ecdsa { 65 } else { 64 };
-    if raw_sig.len() < sig_len || raw_sig.len() % sig_len != 0 {
-        return None;
-    }
-    let mut all_sigs = Vec::new();
-    let sig_num = raw_sig.len() / sig_len as usize;
-    for i in 0..sig_num {
-        let sig = &raw_sig.as_slice()[i * sig_len..(i + 1) * sig_len];
-        all_sigs.push(sig.to_vec());
-    }
-    Some(all_sigs)
-}
+/// Sighashes and the signatures over them.
+pub type BtcMsgsAndSigs = (Vec<Vec<u8>>, Vec<Vec<u8>>);
 
+/// Sighashes of a SCALE encoded `BtcTxMessage` and one compact signature per sighash,
+/// 65 bytes each for ecdsa, 64 bytes for schnorr or 65 for inputs declaring a non-default
+/// sighash type. DER signatures and the sighash byte itself are not checked.
+#[deprecated(note = "use `verify_btc_ecdsa`/`verify_btc_schnorr` of sxn-node-primitives, which split DER signatures and check sighash bytes")]
 pub fn disintegrate_btc_msgs_and_sigs(
     msg: &[u8],
     sig: &[u8],
     is_ecdsa: bool,
-) -> Option<(Vec<Vec<u8>>, Vec<Vec<u8>>)> {
-    let msgs = match disintegrate_btc_msg(&hex::encode(msg)) {
-        Ok(msg) => {
-            let mut msgs = Vec::new();
-            for tx in msg.txs {
-                for hash in tx.hash_to_sign {
-                    match hex::decode(&hash) {
-                        Ok(hash) => msgs.push(hash),
-                        Err(_) => {
-                            return None;
-                        }
-                    }
-                }
+) -> Option<BtcMsgsAndSigs> {
+    let btc_msg = disintegrate_btc_msg(&hex::encode(msg)).ok()?;
+    let mut msgs = Vec::new();
+    let mut sigs = Vec::new();
+    let mut rest = sig;
+    for tx in &btc_msg.txs {
+        for (i, hash) in tx.hash_to_sign.iter().enumerate() {
+            msgs.push(hex::decode(hash).ok()?);
+            let sig_len = match tx.sighash_type(i) {
+                _ if is_ecdsa => 65,
+                None | Some(BtcSighashType::Default) => 64,
+                Some(_) => 65,
+            };
+            if rest.len() < sig_len {
+                return None;
             }
-            msgs
-        }
-        Err(_) => {
-            return None;
+            let (sig, tail) = rest.split_at(sig_len);
+            sigs.push(sig.to_vec());
+            rest = tail;
         }
-    };
-    let sigs = match disintegrate_btc_signatures(sig.to_vec(), is_ecdsa) {
-        Some(sigs) => sigs,
-        None => return None,
-    };
-    if msgs.len() != sigs.len() {
+    }
+    if !rest.is_empty() || msgs.is_empty() {
         return None;
     }
     Some((msgs, sigs))
@@ -177,3 +297,96 @@ where
     tron_message.extend_from_slice(msg);
     keccak256(&tron_message).as_ref().to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(sighash_types: Vec<BtcSighashType>) -> BtcTxMessage {
+        BtcTxMessage {
+            txs: vec![BtcSingleTx {
+                raw_hex: "0200".into(),
+                hash_to_sign: vec!["01".repeat(32), "02".repeat(32)],
+                input_values: vec![1000, 2000],
+                sighash_types,
+            }],
+            asset: AssetType::Runes,
+            taproot_data: Some(vec![7, 8]),
+        }
+    }
+
+    /// Input that does not know its length, like a stream.
+    struct Stream<'a>(&'a [u8]);
+
+    impl Input for Stream<'_> {
+        fn remaining_len(&mut self) -> Result<Option<usize>, CodecError> {
+            Ok(None)
+        }
+
+        fn read(&mut self, into: &mut [u8]) -> Result<(), CodecError> {
+            self.0.read(into)
+        }
+    }
+
+    #[test]
+    fn btc_tx_message_round_trip() {
+        let legacy = message(vec![]);
+        let typed = message(vec![BtcSighashType::All, BtcSighashType::SingleAnyoneCanPay]);
+        let encoded = legacy.encode();
+        assert_eq!(encoded, (&legacy.txs, &legacy.asset, &legacy.taproot_data).encode());
+        assert_eq!(typed.encode()[..2], [BTC_TX_MESSAGE_VERSIONED, BTC_TX_MESSAGE_VERSION]);
+
+        for msg in [legacy, typed] {
+            let encoded = msg.encode();
+            assert_eq!(BtcTxMessage::decode(&mut &encoded[..]), Ok(msg.clone()));
+            assert_eq!(BtcTxMessage::decode(&mut Stream(&encoded)), Ok(msg.clone()));
+            assert_eq!(disintegrate_btc_msg(&hex::encode(&encoded)), Ok(msg.clone()));
+            // followed by another value
+            let nested = (msg.clone(), 42u32).encode();
+            assert_eq!(<(BtcTxMessage, u32)>::decode(&mut &nested[..]), Ok((msg, 42)));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn disintegrate_btc_msgs_and_sigs_splits_compact_signatures() {
+        let hashes = vec![[1u8; 32].to_vec(), [2u8; 32].to_vec()];
+        let legacy = message(vec![]).encode();
+        let ecdsa = [[0x11; 65], [0x22; 65]].concat();
+        assert_eq!(
+            disintegrate_btc_msgs_and_sigs(&legacy, &ecdsa, true),
+            Some((hashes.clone(), vec![vec![0x11; 65], vec![0x22; 65]])),
+        );
+        assert_eq!(disintegrate_btc_msgs_and_sigs(&legacy, &ecdsa[..129], true), None);
+        assert_eq!(
+            disintegrate_btc_msgs_and_sigs(&legacy, &[0x33; 128], false),
+            Some((hashes.clone(), vec![vec![0x33; 64], vec![0x33; 64]])),
+        );
+
+        let typed = message(vec![BtcSighashType::Default, BtcSighashType::SingleAnyoneCanPay]).encode();
+        let schnorr = [&[0x11; 64][..], &[0x22; 65]].concat();
+        assert_eq!(
+            disintegrate_btc_msgs_and_sigs(&typed, &schnorr, false),
+            Some((hashes, vec![vec![0x11; 64], vec![0x22; 65]])),
+        );
+        assert_eq!(disintegrate_btc_msgs_and_sigs(&typed, &schnorr[..128], false), None);
+        assert_eq!(disintegrate_btc_msgs_and_sigs(b"not a message", &schnorr, false), None);
+    }
+
+    #[test]
+    fn btc_tx_message_rejects_malformed_versions() {
+        let encoded = message(vec![BtcSighashType::All, BtcSighashType::All]).encode();
+        let mut version = encoded.clone();
+        version[1] = BTC_TX_MESSAGE_VERSION + 1;
+        assert!(BtcTxMessage::decode(&mut &version[..]).is_err());
+        // sighash types of one tx short
+        let mut short = encoded[..encoded.len() - 4].to_vec();
+        short.extend(Vec::<Vec<BtcSighashType>>::new().encode());
+        assert!(BtcTxMessage::decode(&mut &short[..]).is_err());
+        assert!(BtcTxMessage::decode(&mut &encoded[..encoded.len() - 1]).is_err());
+        assert!(BtcTxMessage::decode(&mut &[][..]).is_err());
+
+        let mismatched = message(vec![BtcSighashType::All]).encode();
+        assert!(disintegrate_btc_msg(&hex::encode(mismatched)).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `btc_schnorr_batch_verify` against a `btc_schnorr_verify` loop over the same inputs.
+//!
+//! `cargo bench --bench btc_schnorr_batch`
+
+use std::time::{Duration, Instant};
+use sxn_node_primitives::{btc_schnorr_batch_verify, btc_schnorr_sign, btc_schnorr_verify, btc_schnorr_xonly_pubkey, sha2_256};
+
+const ROUNDS: usize = 5;
+
+/// `count` signed inputs spread over `keys` distinct keys.
+fn inputs(count: usize, keys: usize) -> Vec<([u8; 32], [u8; 32], [u8; 64])> {
+    (0..count)
+        .map(|i| {
+            let mut seckey = [0x5a; 32];
+            seckey[28..].copy_from_slice(&((i % keys) as u32 + 1).to_be_bytes());
+            let msg = sha2_256(&(i as u32).to_le_bytes());
+            let pubkey = btc_schnorr_xonly_pubkey(&seckey).unwrap();
+            (pubkey, msg, btc_schnorr_sign(&seckey, &msg, &[0u8; 32]).unwrap())
+        })
+        .collect()
+}
+
+/// Fastest of `ROUNDS` runs of `f`.
+fn best(mut f: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    for (count, keys) in [(100, 1), (100, 100), (300, 1), (300, 300), (500, 1), (500, 500)] {
+        let inputs = inputs(count, keys);
+        let items: Vec<(&[u8], &[u8], &[u8])> = inputs.iter().map(|(p, m, s)| (&p[..], &m[..], &s[..])).collect();
+        let looped = best(|| {
+            for (pubkey, msg, sig) in &items {
+                btc_schnorr_verify(pubkey, msg, sig).unwrap();
+            }
+        });
+        let batched = best(|| btc_schnorr_batch_verify(&items).unwrap());
+        println!(
+            "{count:>4} inputs, {keys:>3} keys: loop {looped:>10.2?}, batch {batched:>10.2?}, {:.2}x",
+            looped.as_secs_f64() / batched.as_secs_f64(),
+        );
+    }
+}
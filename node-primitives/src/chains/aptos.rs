@@ -0,0 +1,620 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{boxed::Box, format, string::{String, ToString}, vec, vec::Vec};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    ed25519_verify, sha3_256, VerifyError,
+};
+
+/// Salt of the signing message, hashed with sha3_256 and prepended to the BCS bytes.
+pub const APTOS_RAW_TRANSACTION_SALT: &[u8] = b"APTOS::RawTransaction";
+
+/// Authentication key scheme byte of a single ed25519 key.
+const ED25519_SCHEME: u8 = 0;
+
+/// Type arguments nest at most this deep, as enforced by the Move verifier.
+const MAX_TYPE_TAG_DEPTH: usize = 8;
+
+/// Authentication key of an ed25519 public key, `sha3_256(pubkey || 0x00)`.
+pub fn aptos_authentication_key(pubkey: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = pubkey.to_vec();
+    preimage.push(ED25519_SCHEME);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&sha3_256(&preimage));
+    key
+}
+
+/// Address of an account created for an ed25519 public key, its authentication key
+/// until the key is rotated.
+pub fn aptos_address_from_pubkey(pubkey: &[u8; 32]) -> [u8; 32] {
+    aptos_authentication_key(pubkey)
+}
+
+/// Bytes an ed25519 signer signs for a BCS encoded `RawTransaction`.
+pub fn aptos_signing_message(raw_txn: &[u8]) -> Vec<u8> {
+    let mut msg = sha3_256(APTOS_RAW_TRANSACTION_SALT);
+    msg.extend_from_slice(raw_txn);
+    msg
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AptosTypeTag {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector(Box<AptosTypeTag>),
+    Struct(AptosStructTag),
+}
+
+/// `address::module::name<type_args>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AptosStructTag {
+    pub address: [u8; 32],
+    pub module: String,
+    pub name: String,
+    pub type_args: Vec<AptosTypeTag>,
+}
+
+/// Call of `module_address::module_name::function`, every argument BCS encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AptosEntryFunction {
+    pub module_address: [u8; 32],
+    pub module_name: String,
+    pub function: String,
+    pub ty_args: Vec<AptosTypeTag>,
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AptosScriptArgument {
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Address([u8; 32]),
+    U8Vector(Vec<u8>),
+    Bool(bool),
+    U16(u16),
+    U32(u32),
+    /// Little-endian.
+    U256([u8; 32]),
+    Serialized(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AptosTransactionPayload {
+    Script { code: Vec<u8>, ty_args: Vec<AptosTypeTag>, args: Vec<AptosScriptArgument> },
+    EntryFunction(AptosEntryFunction),
+    /// Multisig account transaction, the entry function is omitted when already stored on chain.
+    Multisig { multisig_address: [u8; 32], entry_function: Option<AptosEntryFunction> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AptosRawTransaction {
+    pub sender: [u8; 32],
+    pub sequence_number: u64,
+    pub payload: AptosTransactionPayload,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    pub expiration_timestamp_secs: u64,
+    pub chain_id: u8,
+}
+
+impl AptosRawTransaction {
+    /// Decode the BCS encoding, rejecting non-canonical encodings and trailing bytes.
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(bytes);
+        let txn = Self {
+            sender: reader.array()?,
+            sequence_number: reader.u64()?,
+            payload: reader.payload()?,
+            max_gas_amount: reader.u64()?,
+            gas_unit_price: reader.u64()?,
+            expiration_timestamp_secs: reader.u64()?,
+            chain_id: reader.u8()?,
+        };
+        if !reader.0.is_empty() {
+            return Err(format!("{} trailing bytes after aptos transaction", reader.0.len()));
+        }
+        Ok(txn)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = self.sender.to_vec();
+        out.extend(self.sequence_number.to_le_bytes());
+        encode_payload(&mut out, &self.payload);
+        out.extend(self.max_gas_amount.to_le_bytes());
+        out.extend(self.gas_unit_price.to_le_bytes());
+        out.extend(self.expiration_timestamp_secs.to_le_bytes());
+        out.push(self.chain_id);
+        out
+    }
+
+    pub fn signing_message(&self) -> Vec<u8> {
+        aptos_signing_message(&self.encode())
+    }
+}
+
+/// Verify the ed25519 signature over a BCS encoded `RawTransaction`.
+pub fn verify_aptos(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    AptosRawTransaction::decode(msg).map_err(|_| VerifyError::InvalidMessage)?;
+    ed25519_verify(pubkey, &aptos_signing_message(msg), sig)
+}
+
+/// [`ChainVerifier`] of Aptos, ed25519 over the salted `RawTransaction`.
+pub struct AptosVerifier;
+
+impl ChainVerifier for AptosVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => {
+                AptosRawTransaction::decode(msg).map_err(|_| VerifyError::InvalidMessage)?;
+                Ok(vec![aptos_signing_message(msg)])
+            }
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => verify_aptos(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+fn encode_uleb128(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    encode_uleb128(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn encode_type_tag(out: &mut Vec<u8>, tag: &AptosTypeTag) {
+    match tag {
+        AptosTypeTag::Bool => out.push(0),
+        AptosTypeTag::U8 => out.push(1),
+        AptosTypeTag::U64 => out.push(2),
+        AptosTypeTag::U128 => out.push(3),
+        AptosTypeTag::Address => out.push(4),
+        AptosTypeTag::Signer => out.push(5),
+        AptosTypeTag::Vector(tag) => {
+            out.push(6);
+            encode_type_tag(out, tag);
+        }
+        AptosTypeTag::Struct(tag) => {
+            out.push(7);
+            out.extend_from_slice(&tag.address);
+            encode_bytes(out, tag.module.as_bytes());
+            encode_bytes(out, tag.name.as_bytes());
+            encode_type_tags(out, &tag.type_args);
+        }
+        AptosTypeTag::U16 => out.push(8),
+        AptosTypeTag::U32 => out.push(9),
+        AptosTypeTag::U256 => out.push(10),
+    }
+}
+
+fn encode_type_tags(out: &mut Vec<u8>, tags: &[AptosTypeTag]) {
+    encode_uleb128(out, tags.len());
+    tags.iter().for_each(|tag| encode_type_tag(out, tag));
+}
+
+fn encode_entry_function(out: &mut Vec<u8>, entry_function: &AptosEntryFunction) {
+    out.extend_from_slice(&entry_function.module_address);
+    encode_bytes(out, entry_function.module_name.as_bytes());
+    encode_bytes(out, entry_function.function.as_bytes());
+    encode_type_tags(out, &entry_function.ty_args);
+    encode_uleb128(out, entry_function.args.len());
+    entry_function.args.iter().for_each(|arg| encode_bytes(out, arg));
+}
+
+fn encode_payload(out: &mut Vec<u8>, payload: &AptosTransactionPayload) {
+    match payload {
+        AptosTransactionPayload::Script { code, ty_args, args } => {
+            out.push(0);
+            encode_bytes(out, code);
+            encode_type_tags(out, ty_args);
+            encode_uleb128(out, args.len());
+            for arg in args {
+                match arg {
+                    AptosScriptArgument::U8(v) => out.extend([0, *v]),
+                    AptosScriptArgument::U64(v) => {
+                        out.push(1);
+                        out.extend(v.to_le_bytes());
+                    }
+                    AptosScriptArgument::U128(v) => {
+                        out.push(2);
+                        out.extend(v.to_le_bytes());
+                    }
+                    AptosScriptArgument::Address(v) => {
+                        out.push(3);
+                        out.extend_from_slice(v);
+                    }
+                    AptosScriptArgument::U8Vector(v) => {
+                        out.push(4);
+                        encode_bytes(out, v);
+                    }
+                    AptosScriptArgument::Bool(v) => out.extend([5, *v as u8]),
+                    AptosScriptArgument::U16(v) => {
+                        out.push(6);
+                        out.extend(v.to_le_bytes());
+                    }
+                    AptosScriptArgument::U32(v) => {
+                        out.push(7);
+                        out.extend(v.to_le_bytes());
+                    }
+                    AptosScriptArgument::U256(v) => {
+                        out.push(8);
+                        out.extend_from_slice(v);
+                    }
+                    AptosScriptArgument::Serialized(v) => {
+                        out.push(9);
+                        encode_bytes(out, v);
+                    }
+                }
+            }
+        }
+        AptosTransactionPayload::EntryFunction(entry_function) => {
+            out.push(2);
+            encode_entry_function(out, entry_function);
+        }
+        AptosTransactionPayload::Multisig { multisig_address, entry_function } => {
+            out.push(3);
+            out.extend_from_slice(multisig_address);
+            match entry_function {
+                Some(entry_function) => {
+                    // `Some` of the only `MultisigTransactionPayload` variant
+                    out.extend([1, 0]);
+                    encode_entry_function(out, entry_function);
+                }
+                None => out.push(0),
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("unexpected end of aptos transaction".to_string());
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(format!("invalid bcs bool: {b}")),
+        }
+    }
+
+    /// Canonical ULEB128 of at most `u32::MAX`, as BCS lengths and variant indexes are.
+    fn uleb128(&mut self) -> Result<usize, String> {
+        let mut value = 0u64;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                if shift > 0 && byte == 0 {
+                    return Err("non-canonical bcs uleb128".to_string());
+                }
+                if value > u32::MAX as u64 {
+                    return Err("bcs uleb128 overflow".to_string());
+                }
+                return Ok(value as usize);
+            }
+        }
+        Err("bcs uleb128 overflow".to_string())
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.uleb128()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        let len = self.uleb128()?;
+        // every item takes at least one byte
+        if len > self.0.len() {
+            return Err("unexpected end of aptos transaction".to_string());
+        }
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    /// Move identifier, `[a-zA-Z_][a-zA-Z0-9_]*` and not a lone `_`.
+    fn identifier(&mut self) -> Result<String, String> {
+        let bytes = self.bytes()?;
+        let valid = match bytes.split_first() {
+            Some((first, rest)) => {
+                (first.is_ascii_alphabetic() || (*first == b'_' && !rest.is_empty()))
+                    && rest.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            }
+            None => false,
+        };
+        if !valid {
+            return Err(format!("invalid move identifier: {:?}", String::from_utf8_lossy(&bytes)));
+        }
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+
+    fn type_tag(&mut self, depth: usize) -> Result<AptosTypeTag, String> {
+        if depth > MAX_TYPE_TAG_DEPTH {
+            return Err("aptos type tag nested too deep".to_string());
+        }
+        Ok(match self.uleb128()? {
+            0 => AptosTypeTag::Bool,
+            1 => AptosTypeTag::U8,
+            2 => AptosTypeTag::U64,
+            3 => AptosTypeTag::U128,
+            4 => AptosTypeTag::Address,
+            5 => AptosTypeTag::Signer,
+            6 => AptosTypeTag::Vector(Box::new(self.type_tag(depth + 1)?)),
+            7 => AptosTypeTag::Struct(AptosStructTag {
+                address: self.array()?,
+                module: self.identifier()?,
+                name: self.identifier()?,
+                type_args: self.vec(|r| r.type_tag(depth + 1))?,
+            }),
+            8 => AptosTypeTag::U16,
+            9 => AptosTypeTag::U32,
+            10 => AptosTypeTag::U256,
+            v => return Err(format!("unknown aptos type tag: {v}")),
+        })
+    }
+
+    fn entry_function(&mut self) -> Result<AptosEntryFunction, String> {
+        Ok(AptosEntryFunction {
+            module_address: self.array()?,
+            module_name: self.identifier()?,
+            function: self.identifier()?,
+            ty_args: self.vec(|r| r.type_tag(1))?,
+            args: self.vec(|r| r.bytes())?,
+        })
+    }
+
+    fn script_argument(&mut self) -> Result<AptosScriptArgument, String> {
+        Ok(match self.uleb128()? {
+            0 => AptosScriptArgument::U8(self.u8()?),
+            1 => AptosScriptArgument::U64(self.u64()?),
+            2 => AptosScriptArgument::U128(u128::from_le_bytes(self.array()?)),
+            3 => AptosScriptArgument::Address(self.array()?),
+            4 => AptosScriptArgument::U8Vector(self.bytes()?),
+            5 => AptosScriptArgument::Bool(self.bool()?),
+            6 => AptosScriptArgument::U16(u16::from_le_bytes(self.array()?)),
+            7 => AptosScriptArgument::U32(u32::from_le_bytes(self.array()?)),
+            8 => AptosScriptArgument::U256(self.array()?),
+            9 => AptosScriptArgument::Serialized(self.bytes()?),
+            v => return Err(format!("unknown aptos script argument: {v}")),
+        })
+    }
+
+    fn payload(&mut self) -> Result<AptosTransactionPayload, String> {
+        Ok(match self.uleb128()? {
+            0 => AptosTransactionPayload::Script {
+                code: self.bytes()?,
+                ty_args: self.vec(|r| r.type_tag(1))?,
+                args: self.vec(|r| r.script_argument())?,
+            },
+            2 => AptosTransactionPayload::EntryFunction(self.entry_function()?),
+            3 => AptosTransactionPayload::Multisig {
+                multisig_address: self.array()?,
+                entry_function: match self.u8()? {
+                    0 => None,
+                    1 => match self.uleb128()? {
+                        0 => Some(self.entry_function()?),
+                        v => return Err(format!("unknown aptos multisig payload: {v}")),
+                    },
+                    b => return Err(format!("invalid bcs option tag: {b}")),
+                },
+            },
+            v => return Err(format!("unsupported aptos transaction payload: {v}")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+    const AUTH_KEY: &str = "7df415e5b21bdaa8b2946e8f1f4278b39904e51a69627494cd3e6f2996732fbd";
+    // 0x1::aptos_account::transfer([0x02; 32], 1000), signed with the ed25519 seed [0x01; 32]
+    const TRANSFER: &str = "7df415e5b21bdaa8b2946e8f1f4278b39904e51a69627494cd3e6f2996732fbd07000000000000000200000000000000000000000000000000000000000000000000000000000000010d6170746f735f6163636f756e74087472616e73666572000220020202020202020202020202020202020202020202020202020202020202020208e803000000000000d007000000000000640000000000000000f153650000000001";
+    const TRANSFER_SIG: &str = "c7d5cdb2a87e3a063bb90f7eaf3c9081eb5f35b65c0f5a3d4d3eb1844bcbfc8a2c0de23e20514dae159cb638651d6d82f95f2b63c3aff43489a8b6329c59f108";
+    // 0x1::coin::transfer<0x1::aptos_coin::AptosCoin>([0x02; 32], 1000)
+    const COIN_TRANSFER: &str = "7df415e5b21bdaa8b2946e8f1f4278b39904e51a69627494cd3e6f2996732fbd070000000000000002000000000000000000000000000000000000000000000000000000000000000104636f696e087472616e73666572010700000000000000000000000000000000000000000000000000000000000000010a6170746f735f636f696e094170746f73436f696e000220020202020202020202020202020202020202020202020202020202020202020208e803000000000000d007000000000000640000000000000000f153650000000001";
+    const COIN_TRANSFER_SIG: &str = "6dfce89e9f23630597d84ee993cfbb383de0ed6fe92ee09991c98dbd34ef560119d9a8724d0cb39cc7e4d679dd93d692951a08ba594ffc62d0fdfb0a61061107";
+
+    fn pubkey() -> [u8; 32] {
+        hex::decode(PUBKEY).unwrap().try_into().unwrap()
+    }
+
+    fn framework() -> [u8; 32] {
+        let mut address = [0u8; 32];
+        address[31] = 1;
+        address
+    }
+
+    #[test]
+    fn authentication_key_and_signing_prefix() {
+        assert_eq!(hex::encode(aptos_authentication_key(&pubkey())), AUTH_KEY);
+        assert_eq!(aptos_address_from_pubkey(&pubkey()), aptos_authentication_key(&pubkey()));
+        assert_eq!(
+            hex::encode(aptos_signing_message(&[])),
+            "b5e97db07fa0bd0e5598aa3643a9bc6f6693bddc1a9fec9e674a461eaa00b193",
+        );
+    }
+
+    #[test]
+    fn decode_entry_function_transactions() {
+        let bytes = hex::decode(TRANSFER).unwrap();
+        let txn = AptosRawTransaction::decode(&bytes).unwrap();
+        assert_eq!(txn.sender, aptos_address_from_pubkey(&pubkey()));
+        assert_eq!(txn.sequence_number, 7);
+        assert_eq!((txn.max_gas_amount, txn.gas_unit_price), (2000, 100));
+        assert_eq!(txn.expiration_timestamp_secs, 1_700_000_000);
+        assert_eq!(txn.chain_id, 1);
+        assert_eq!(
+            txn.payload,
+            AptosTransactionPayload::EntryFunction(AptosEntryFunction {
+                module_address: framework(),
+                module_name: "aptos_account".into(),
+                function: "transfer".into(),
+                ty_args: vec![],
+                args: vec![vec![2; 32], 1000u64.to_le_bytes().to_vec()],
+            }),
+        );
+        assert_eq!(txn.encode(), bytes);
+
+        let bytes = hex::decode(COIN_TRANSFER).unwrap();
+        let txn = AptosRawTransaction::decode(&bytes).unwrap();
+        let AptosTransactionPayload::EntryFunction(entry_function) = &txn.payload else {
+            panic!("expected an entry function payload");
+        };
+        assert_eq!(
+            entry_function.ty_args,
+            vec![AptosTypeTag::Struct(AptosStructTag {
+                address: framework(),
+                module: "aptos_coin".into(),
+                name: "AptosCoin".into(),
+                type_args: vec![],
+            })],
+        );
+        assert_eq!(txn.encode(), bytes);
+    }
+
+    #[test]
+    fn encode_round_trips_other_payloads() {
+        let txn = AptosRawTransaction::decode(&hex::decode(TRANSFER).unwrap()).unwrap();
+        let AptosTransactionPayload::EntryFunction(entry_function) = txn.payload.clone() else {
+            panic!("expected an entry function payload");
+        };
+        let nested = AptosTypeTag::Vector(Box::new(AptosTypeTag::Vector(Box::new(AptosTypeTag::U256))));
+        for payload in [
+            AptosTransactionPayload::Script {
+                code: vec![0xa1, 0x1c, 0xeb, 0x0b],
+                ty_args: vec![nested, AptosTypeTag::Signer],
+                args: vec![
+                    AptosScriptArgument::U8(1),
+                    AptosScriptArgument::U16(2),
+                    AptosScriptArgument::U128(3),
+                    AptosScriptArgument::U256([4; 32]),
+                    AptosScriptArgument::Address([5; 32]),
+                    AptosScriptArgument::U8Vector(vec![6; 200]),
+                    AptosScriptArgument::Bool(true),
+                    AptosScriptArgument::Serialized(vec![7]),
+                ],
+            },
+            AptosTransactionPayload::Multisig { multisig_address: [8; 32], entry_function: Some(entry_function) },
+            AptosTransactionPayload::Multisig { multisig_address: [8; 32], entry_function: None },
+        ] {
+            let txn = AptosRawTransaction { payload, ..txn.clone() };
+            assert_eq!(AptosRawTransaction::decode(&txn.encode()), Ok(txn));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_malformed_transactions() {
+        let bytes = hex::decode(TRANSFER).unwrap();
+        for len in [0, 31, 40, 41, 100, bytes.len() - 1] {
+            assert!(AptosRawTransaction::decode(&bytes[..len]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(AptosRawTransaction::decode(&trailing).is_err());
+
+        // deprecated module bundle payload
+        let mut module_bundle = bytes.clone();
+        module_bundle[40] = 1;
+        assert!(AptosRawTransaction::decode(&module_bundle).is_err());
+
+        // `aptos-account` is not a move identifier
+        let mut identifier = bytes.clone();
+        identifier[79] = b'-';
+        assert!(AptosRawTransaction::decode(&identifier).is_err());
+
+        // aliased uleb128 for the number of type arguments
+        let mut aliased = bytes[..96].to_vec();
+        aliased.extend([0x80, 0x00]);
+        aliased.extend_from_slice(&bytes[97..]);
+        assert_eq!(bytes[96], 0);
+        assert!(AptosRawTransaction::decode(&aliased).is_err());
+
+        let mut tag = AptosTypeTag::U8;
+        for _ in 0..MAX_TYPE_TAG_DEPTH {
+            tag = AptosTypeTag::Vector(Box::new(tag));
+        }
+        let txn = AptosRawTransaction::decode(&bytes).unwrap();
+        let txn = AptosRawTransaction {
+            payload: AptosTransactionPayload::Script { code: vec![], ty_args: vec![tag], args: vec![] },
+            ..txn
+        };
+        assert!(AptosRawTransaction::decode(&txn.encode()).is_err());
+    }
+
+    #[test]
+    fn verify_signed_transactions() {
+        for (txn, sig) in [(TRANSFER, TRANSFER_SIG), (COIN_TRANSFER, COIN_TRANSFER_SIG)] {
+            let msg = hex::decode(txn).unwrap();
+            let sig = hex::decode(sig).unwrap();
+            assert_eq!(verify_aptos(&pubkey(), &msg, &sig), Ok(()));
+            // the signature covers the salted message, not the bare transaction
+            assert_eq!(ed25519_verify(&pubkey(), &msg, &sig), Err(VerifyError::BadSignature));
+            assert_eq!(verify_aptos(&pubkey(), &msg[1..], &sig), Err(VerifyError::InvalidMessage));
+
+            let mut tampered = AptosRawTransaction::decode(&msg).unwrap();
+            tampered.sequence_number += 1;
+            assert_eq!(verify_aptos(&pubkey(), &tampered.encode(), &sig), Err(VerifyError::BadSignature));
+        }
+    }
+}
@@ -15,28 +15,466 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::string::{ToString, String};
-use chain_bridge::utils::disintegrate_btc_msgs_and_sigs;
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use chain_bridge::utils::{disintegrate_btc_msg, BtcSighashType};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    crypto::{parse_ecdsa_pubkey, parse_ecdsa_signature},
+    verify_btc_schnorr, LowSPolicy, VerifyError,
+};
 
-pub fn verify_btc_ecdsa(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
-    let (msgs, sigs) = match disintegrate_btc_msgs_and_sigs(msg, sig, true) {
-        Some(data) => data,
+/// Bitcoin relays only low-S signatures (BIP146).
+pub const BTC_LOW_S_POLICY: LowSPolicy = LowSPolicy::Strict;
+
+/// Verify one ecdsa signature per sighash of a SCALE encoded `BtcTxMessage`.
+///
+/// Signatures are concatenated, either 65-byte `r || s || v` each (`r || s || sighash` for
+/// inputs declaring a sighash type) or strict BIP66 `DER || sighash` each.
+pub fn verify_btc_ecdsa(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    inner_verify_btc_ecdsa(pubkey, msg, sig, BTC_LOW_S_POLICY).map(|_| ())
+}
+
+/// [`verify_btc_ecdsa`], returning the sighash type each signature committed to.
+///
+/// Compact signatures of inputs without a declared sighash type are reported as `SIGHASH_ALL`.
+pub fn verify_btc_ecdsa_sighash_types(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<Vec<BtcSighashType>, VerifyError> {
+    inner_verify_btc_ecdsa(pubkey, msg, sig, BTC_LOW_S_POLICY)
+}
+
+pub(crate) fn inner_verify_btc_ecdsa(
+    pubkey: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+    policy: LowSPolicy,
+) -> Result<Vec<BtcSighashType>, VerifyError> {
+    let inputs = disintegrate_btc_sighashes(msg)?;
+    let sigs = disintegrate_btc_ecdsa_signatures(sig, &inputs)?;
+    let pubkey = parse_ecdsa_pubkey(pubkey)?;
+    let mut sighash_types = Vec::with_capacity(sigs.len());
+    for (i, (input, (sig, sighash_type))) in inputs.iter().zip(sigs).enumerate() {
+        let message = secp256k1::Message::parse(&input.hash);
+        let signature = parse_ecdsa_signature(&sig, policy).map_err(|e| VerifyError::at_index(i, e))?;
+        if !secp256k1::verify(&message, &signature, &pubkey) {
+            return Err(VerifyError::at_index(i, VerifyError::BadSignature));
+        }
+        sighash_types.push(sighash_type);
+    }
+    Ok(sighash_types)
+}
+
+/// [`ChainVerifier`] of Bitcoin, one ECDSA or BIP340 signature per input sighash.
+pub struct BtcVerifier;
+
+impl ChainVerifier for BtcVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa | SignatureScheme::Schnorr => btc_signing_hashes(msg),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => verify_btc_ecdsa(pubkey, msg, sig),
+            SignatureScheme::Schnorr => verify_btc_schnorr(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+pub(crate) fn btc_signing_hashes(msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+    Ok(disintegrate_btc_sighashes(msg)?.into_iter().map(|input| input.hash.to_vec()).collect())
+}
+
+/// Sighash of one input and the sighash type the message declares for it.
+pub(crate) struct BtcInputSighash {
+    pub hash: [u8; 32],
+    pub sighash_type: Option<BtcSighashType>,
+}
+
+/// Sighashes of a SCALE encoded `BtcTxMessage`, in input order.
+pub(crate) fn disintegrate_btc_sighashes(msg: &[u8]) -> Result<Vec<BtcInputSighash>, VerifyError> {
+    let btc_msg = disintegrate_btc_msg(&hex::encode(msg)).map_err(|_| VerifyError::InvalidMessage)?;
+    let mut inputs = Vec::new();
+    for tx in btc_msg.txs {
+        for (i, hash) in tx.hash_to_sign.iter().enumerate() {
+            let hash = hex::decode(hash).map_err(|_| VerifyError::InvalidMessage)?;
+            let hash: [u8; 32] = hash.try_into().map_err(|_| VerifyError::InvalidMessage)?;
+            inputs.push(BtcInputSighash { hash, sighash_type: tx.sighash_type(i) });
+        }
+    }
+    if inputs.is_empty() {
+        return Err(VerifyError::InvalidMessage);
+    }
+    Ok(inputs)
+}
+
+/// Encode a compact `r || s` secp256k1 signature as strict BIP66 DER followed by the
+/// `sighash_type` byte, the form pushed in a scriptSig or witness.
+pub fn btc_der_encode_signature(sig: &[u8; 64], sighash_type: BtcSighashType) -> Result<Vec<u8>, VerifyError> {
+    if sighash_type == BtcSighashType::Default || sig.chunks(32).any(|int| int.iter().all(|b| *b == 0)) {
+        return Err(VerifyError::InvalidSignature);
+    }
+    parse_ecdsa_signature(sig, LowSPolicy::Allow)?;
+    let mut der = vec![0x30, 0];
+    for int in sig.chunks(32) {
+        let start = int.iter().position(|b| *b != 0).expect("zero r and s are rejected above; qed");
+        let pad = int[start] & 0x80 != 0;
+        der.push(0x02);
+        der.push((32 - start + pad as usize) as u8);
+        if pad {
+            der.push(0);
+        }
+        der.extend_from_slice(&int[start..]);
+    }
+    der[1] = (der.len() - 2) as u8;
+    der.push(sighash_type.to_u8());
+    Ok(der)
+}
+
+/// Decode a strict BIP66 DER signature, without sighash byte, into compact `r || s`.
+pub fn btc_der_decode_signature(der: &[u8]) -> Result<[u8; 64], VerifyError> {
+    // BIP66 `IsValidSignatureEncoding` with the sighash byte stripped
+    let len = der.len();
+    if !(8..=72).contains(&len) {
+        return Err(VerifyError::InvalidSignatureLength { expected: 72, actual: len as u32 });
+    }
+    // sequence tag and length
+    if der[0] != 0x30 || der[1] as usize != len - 2 {
+        return Err(VerifyError::InvalidSignature);
+    }
+    // r and s lengths cover the sequence exactly
+    let len_r = der[3] as usize;
+    if 5 + len_r >= len {
+        return Err(VerifyError::InvalidSignature);
+    }
+    let len_s = der[5 + len_r] as usize;
+    if len_r + len_s + 6 != len {
+        return Err(VerifyError::InvalidSignature);
+    }
+    let r = &der[4..4 + len_r];
+    let s = &der[6 + len_r..];
+    if der[2] != 0x02 || der[4 + len_r] != 0x02 {
+        return Err(VerifyError::InvalidSignature);
+    }
+    let mut compact = [0u8; 64];
+    let (r_out, s_out) = compact.split_at_mut(32);
+    for (int, out) in [(r, r_out), (s, s_out)] {
+        // empty or negative
+        if int.is_empty() || int[0] & 0x80 != 0 {
+            return Err(VerifyError::InvalidSignature);
+        }
+        // surplus zero padding
+        if int.len() > 1 && int[0] == 0 && int[1] & 0x80 == 0 {
+            return Err(VerifyError::InvalidSignature);
+        }
+        let int = if int[0] == 0 { &int[1..] } else { int };
+        if int.len() > 32 {
+            return Err(VerifyError::InvalidSignature);
+        }
+        out[32 - int.len()..].copy_from_slice(int);
+    }
+    Ok(compact)
+}
+
+/// Split concatenated `DER || sighash` signatures into compact `r || s` and sighash byte,
+/// `None` unless the whole input is strictly encoded.
+pub fn btc_split_der_signatures(raw_sig: &[u8]) -> Option<Vec<([u8; 64], u8)>> {
+    let mut sigs = Vec::new();
+    let mut rest = raw_sig;
+    while !rest.is_empty() {
+        if rest.len() < 2 || rest[0] != 0x30 {
+            return None;
+        }
+        let der_len = rest[1] as usize + 2;
+        if rest.len() <= der_len {
+            return None;
+        }
+        let compact = btc_der_decode_signature(&rest[..der_len]).ok()?;
+        sigs.push((compact, rest[der_len]));
+        rest = &rest[der_len + 1..];
+    }
+    if sigs.is_empty() {
+        return None;
+    }
+    Some(sigs)
+}
+
+/// Split one ecdsa signature per input into compact `r || s` and sighash type.
+///
+/// Without a declared sighash type the byte after a compact signature is ignored and
+/// `SIGHASH_ALL` reported, with one it must be the declared type.
+fn disintegrate_btc_ecdsa_signatures(
+    sig: &[u8],
+    inputs: &[BtcInputSighash],
+) -> Result<Vec<([u8; 64], BtcSighashType)>, VerifyError> {
+    let sigs = match btc_split_der_signatures(sig) {
+        Some(sigs) => {
+            if sigs.len() != inputs.len() {
+                return Err(VerifyError::InvalidSignature);
+            }
+            sigs
+        }
         None => {
-            return Err("disintegrate btc msgs and sigs failed".to_string());
+            if sig.len() != inputs.len() * 65 {
+                return Err(VerifyError::InvalidSignatureLength {
+                    expected: (inputs.len() * 65) as u32,
+                    actual: sig.len() as u32,
+                });
+            }
+            sig.chunks(65)
+                .zip(inputs)
+                .map(|(sig, input)| {
+                    let mut compact = [0u8; 64];
+                    compact.copy_from_slice(&sig[..64]);
+                    let sighash = input.sighash_type.map_or(BtcSighashType::All.to_u8(), |_| sig[64]);
+                    (compact, sighash)
+                })
+                .collect()
         }
     };
-    if msgs.len() != sigs.len() {
-        return Err("invalid length for btc msgs and sigs".to_string());
-    }
-    for i in 0..msgs.len() {
-        let mut msg = [0u8; 32];
-        msg.copy_from_slice(&msgs[i]);
-        let message = secp256k1::Message::parse(&msg);
-        let signature = secp256k1::Signature::parse_slice(&sigs[i][..64]).map_err(|e| e.to_string())?;
-        let pubkey = secp256k1::PublicKey::parse_slice(pubkey, None).map_err(|e| e.to_string())?;
-        if !secp256k1::verify(&message, &signature, &pubkey) {
-            return Err("btc ecdsa signature verify failed".to_string());
+    sigs.into_iter()
+        .zip(inputs)
+        .enumerate()
+        .map(|(i, ((compact, sighash), input))| match BtcSighashType::from_u8(sighash) {
+            Some(BtcSighashType::Default) | None => Err(VerifyError::at_index(i, VerifyError::InvalidSignature)),
+            Some(sighash_type) if input.sighash_type.is_some_and(|declared| declared != sighash_type) => {
+                Err(VerifyError::at_index(i, VerifyError::InvalidSignature))
+            }
+            Some(sighash_type) => Ok((compact, sighash_type)),
+        })
+        .collect()
+}
+
+/// Sighashes and the signatures over them, in input order.
+pub(crate) type BtcSchnorrVerifyData = (Vec<[u8; 32]>, Vec<[u8; 64]>);
+
+/// Split `sig` into one schnorr signature per input of a SCALE encoded `BtcTxMessage`,
+/// 64 bytes for `SIGHASH_DEFAULT` inputs, 65 bytes ending with the declared sighash type
+/// otherwise.
+pub(crate) fn disintegrate_btc_schnorr_verify_data(msg: &[u8], sig: &[u8]) -> Result<BtcSchnorrVerifyData, VerifyError> {
+    let inputs = disintegrate_btc_sighashes(msg)?;
+    let sig_len = |input: &BtcInputSighash| match input.sighash_type {
+        None | Some(BtcSighashType::Default) => 64,
+        Some(_) => 65,
+    };
+    let expected: usize = inputs.iter().map(sig_len).sum();
+    if sig.len() != expected {
+        return Err(VerifyError::InvalidSignatureLength { expected: expected as u32, actual: sig.len() as u32 });
+    }
+    let mut msgs = Vec::with_capacity(inputs.len());
+    let mut sigs = Vec::with_capacity(inputs.len());
+    let mut rest = sig;
+    for (i, input) in inputs.into_iter().enumerate() {
+        let (sig, tail) = rest.split_at(sig_len(&input));
+        if sig.len() == 65 && input.sighash_type.map(BtcSighashType::to_u8) != Some(sig[64]) {
+            return Err(VerifyError::at_index(i, VerifyError::InvalidSignature));
+        }
+        let mut schnorr_sig = [0u8; 64];
+        schnorr_sig.copy_from_slice(&sig[..64]);
+        msgs.push(input.hash);
+        sigs.push(schnorr_sig);
+        rest = tail;
+    }
+    Ok((msgs, sigs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_bridge::utils::{AssetType, BtcSingleTx, BtcTxMessage};
+    use codec::Encode;
+
+    const SECKEY: [u8; 32] = [7u8; 32];
+
+    fn message(hashes: &[[u8; 32]], sighash_types: Vec<BtcSighashType>) -> Vec<u8> {
+        let tx = BtcSingleTx {
+            raw_hex: "00".into(),
+            hash_to_sign: hashes.iter().map(hex::encode).collect(),
+            input_values: hashes.iter().map(|_| 1000).collect(),
+            sighash_types,
+        };
+        BtcTxMessage { txs: vec![tx], asset: AssetType::Native, taproot_data: None }.encode()
+    }
+
+    fn sign(hash: &[u8; 32]) -> secp256k1::Signature {
+        let sk = secp256k1::SecretKey::parse(&SECKEY).unwrap();
+        secp256k1::sign(&secp256k1::Message::parse(hash), &sk).0
+    }
+
+    fn pubkey() -> Vec<u8> {
+        let sk = secp256k1::SecretKey::parse(&SECKEY).unwrap();
+        secp256k1::PublicKey::from_secret_key(&sk).serialize_compressed().to_vec()
+    }
+
+    /// DER of `r` and `s` given as big-endian integer bodies, encoded as is.
+    fn der(r: &[u8], s: &[u8]) -> Vec<u8> {
+        let mut der = vec![0x30, (r.len() + s.len() + 4) as u8, 0x02, r.len() as u8];
+        der.extend_from_slice(r);
+        der.extend_from_slice(&[0x02, s.len() as u8]);
+        der.extend_from_slice(s);
+        der
+    }
+
+    #[test]
+    fn der_decode_accepts_strict_encodings() {
+        let signature = sign(&[1u8; 32]);
+        let der_sig = signature.serialize_der();
+        assert_eq!(btc_der_decode_signature(der_sig.as_ref()), Ok(signature.serialize()));
+
+        // shortest integers, and a high bit set integer padded with one zero byte
+        let mut compact = [0u8; 64];
+        compact[31] = 1;
+        compact[32] = 0x80;
+        let mut s = vec![0x00];
+        s.extend_from_slice(&compact[32..]);
+        assert_eq!(btc_der_decode_signature(&der(&[1], &s)), Ok(compact));
+        compact[32] = 0;
+        compact[63] = 5;
+        assert_eq!(btc_der_decode_signature(&der(&[1], &[5])), Ok(compact));
+    }
+
+    #[test]
+    fn der_encode_round_trips() {
+        for (i, sighash_type) in [BtcSighashType::All, BtcSighashType::NoneAnyoneCanPay].into_iter().enumerate() {
+            let signature = sign(&[i as u8 + 1; 32]);
+            let encoded = btc_der_encode_signature(&signature.serialize(), sighash_type).unwrap();
+            assert_eq!(encoded, [signature.serialize_der().as_ref(), &[sighash_type.to_u8()]].concat());
+            assert_eq!(btc_der_decode_signature(&encoded[..encoded.len() - 1]), Ok(signature.serialize()));
+            assert_eq!(btc_split_der_signatures(&encoded), Some(vec![(signature.serialize(), sighash_type.to_u8())]));
+        }
+
+        // shortest integers, padded when the high bit is set
+        let mut compact = [0u8; 64];
+        compact[31] = 1;
+        compact[32] = 0x80;
+        let mut s = vec![0x00];
+        s.extend_from_slice(&compact[32..]);
+        let encoded = btc_der_encode_signature(&compact, BtcSighashType::Single).unwrap();
+        assert_eq!(encoded, [&der(&[1], &s)[..], &[0x03]].concat());
+        assert_eq!(btc_der_decode_signature(&encoded[..encoded.len() - 1]), Ok(compact));
+
+        // zero or overflowing scalars and SIGHASH_DEFAULT have no ecdsa encoding
+        let mut zero = compact;
+        zero[..32].fill(0);
+        assert_eq!(btc_der_encode_signature(&zero, BtcSighashType::All), Err(VerifyError::InvalidSignature));
+        let mut overflow = compact;
+        overflow[32..].fill(0xff);
+        assert_eq!(btc_der_encode_signature(&overflow, BtcSighashType::All), Err(VerifyError::InvalidSignature));
+        assert_eq!(btc_der_encode_signature(&compact, BtcSighashType::Default), Err(VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn der_decode_rejects_non_bip66_encodings() {
+        let valid = der(&[1], &[1]);
+        assert!(btc_der_decode_signature(&valid).is_ok());
+        let cases: Vec<Vec<u8>> = vec![
+            // negative r and s
+            der(&[0x80], &[1]),
+            der(&[1], &[0xff]),
+            // surplus zero padding
+            der(&[0, 1], &[1]),
+            der(&[1], &[0, 0x7f]),
+            // zero length integers
+            der(&[], &[1, 1]),
+            der(&[1, 1], &[]),
+            // integer wider than 32 bytes
+            der(&[1; 33], &[1]),
+            // wrong integer tags and sequence tag
+            [&valid[..2], &[0x03], &valid[3..]].concat(),
+            [&valid[..5], &[0x03], &valid[6..]].concat(),
+            [&[0x31], &valid[1..]].concat(),
+            // sequence length not covering the signature
+            [&valid[..1], &[7], &valid[2..]].concat(),
+            [&valid[..1], &[5], &valid[2..]].concat(),
+            // r length running into s, s length short of the end
+            [&valid[..3], &[2], &valid[4..]].concat(),
+            [&valid[..3], &[3], &valid[4..]].concat(),
+            [&valid[..6], &[2], &valid[7..]].concat(),
+            // trailing byte, and too short or too long overall
+            [&valid[..], &[0]].concat(),
+            valid[..7].to_vec(),
+            der(&[1; 33], &[1; 34]),
+        ];
+        for der_sig in cases {
+            assert!(btc_der_decode_signature(&der_sig).is_err(), "{}", hex::encode(&der_sig));
         }
     }
-    Ok(())
+
+    #[test]
+    fn split_der_signatures() {
+        let first = sign(&[1u8; 32]);
+        let second = sign(&[2u8; 32]);
+        let mut raw = first.serialize_der().as_ref().to_vec();
+        raw.push(0x01);
+        raw.extend_from_slice(second.serialize_der().as_ref());
+        raw.push(0x83);
+        assert_eq!(
+            btc_split_der_signatures(&raw),
+            Some(vec![(first.serialize(), 0x01), (second.serialize(), 0x83)]),
+        );
+
+        // missing sighash byte, trailing bytes, empty input and compact signatures
+        assert_eq!(btc_split_der_signatures(&raw[..raw.len() - 1]), None);
+        assert_eq!(btc_split_der_signatures(&[&raw[..], &[0x30]].concat()), None);
+        assert_eq!(btc_split_der_signatures(&[]), None);
+        let mut compact = first.serialize().to_vec();
+        compact.push(0x01);
+        assert_eq!(btc_split_der_signatures(&compact), None);
+        // one non strict signature fails the whole input
+        let mut padded = der(&[0, 1], &[1]);
+        padded.push(0x01);
+        assert_eq!(btc_split_der_signatures(&[&raw[..], &padded[..]].concat()), None);
+    }
+
+    #[test]
+    fn ecdsa_sighash_types() {
+        let hashes = [[1u8; 32], [2u8; 32]];
+        let sigs: Vec<_> = hashes.iter().map(sign).collect();
+        let compact = |bytes: [u8; 2]| -> Vec<u8> {
+            sigs.iter().zip(bytes).flat_map(|(sig, byte)| [&sig.serialize()[..], &[byte]].concat()).collect()
+        };
+        let der_sigs = |bytes: [u8; 2]| -> Vec<u8> {
+            sigs.iter().zip(bytes).flat_map(|(sig, byte)| [sig.serialize_der().as_ref(), &[byte]].concat()).collect()
+        };
+        let all = vec![BtcSighashType::All; 2];
+
+        // undeclared: any byte after a compact signature, a defined non-default one after DER
+        let undeclared = message(&hashes, vec![]);
+        assert_eq!(verify_btc_ecdsa_sighash_types(&pubkey(), &undeclared, &compact([0, 0x55])), Ok(all.clone()));
+        assert_eq!(
+            verify_btc_ecdsa_sighash_types(&pubkey(), &undeclared, &der_sigs([0x01, 0x83])),
+            Ok(vec![BtcSighashType::All, BtcSighashType::SingleAnyoneCanPay]),
+        );
+        for byte in [0x00, 0x04, 0x80] {
+            assert_eq!(
+                verify_btc_ecdsa(&pubkey(), &undeclared, &der_sigs([0x01, byte])),
+                Err(VerifyError::at_index(1, VerifyError::InvalidSignature)),
+            );
+        }
+
+        // declared: both forms must carry the declared byte
+        let types = vec![BtcSighashType::All, BtcSighashType::NoneAnyoneCanPay];
+        let declared = message(&hashes, types.clone());
+        assert_eq!(verify_btc_ecdsa_sighash_types(&pubkey(), &declared, &compact([0x01, 0x82])), Ok(types.clone()));
+        assert_eq!(verify_btc_ecdsa_sighash_types(&pubkey(), &declared, &der_sigs([0x01, 0x82])), Ok(types));
+        for sig in [compact([0x01, 0x02]), der_sigs([0x01, 0x02])] {
+            assert_eq!(
+                verify_btc_ecdsa(&pubkey(), &declared, &sig),
+                Err(VerifyError::at_index(1, VerifyError::InvalidSignature)),
+            );
+        }
+        let default = message(&hashes, vec![BtcSighashType::All, BtcSighashType::Default]);
+        assert_eq!(
+            verify_btc_ecdsa(&pubkey(), &default, &compact([0x01, 0x00])),
+            Err(VerifyError::at_index(1, VerifyError::InvalidSignature)),
+        );
+
+        // one signature per input
+        assert!(verify_btc_ecdsa(&pubkey(), &undeclared, &der_sigs([0x01, 0x01])[..72]).is_err());
+        assert_eq!(
+            verify_btc_ecdsa(&pubkey(), &undeclared, &compact([0x01, 0x01])[..65]),
+            Err(VerifyError::InvalidSignatureLength { expected: 130, actual: 65 }),
+        );
+    }
 }
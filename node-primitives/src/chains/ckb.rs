@@ -15,9 +15,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::string::String;
-use crate::chains::btc::verify_btc_ecdsa;
+use sp_core::bounded::alloc::vec::Vec;
+use crate::{
+    chains::{btc::{btc_signing_hashes, inner_verify_btc_ecdsa}, ChainVerifier, SignatureScheme},
+    LowSPolicy, VerifyError,
+};
 
-pub fn verify_ckb_ecdsa(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
-    verify_btc_ecdsa(pubkey, msg, sig)
+/// The CKB secp256k1 lock recovers the signer and accepts either form of `s`.
+pub const CKB_LOW_S_POLICY: LowSPolicy = LowSPolicy::Allow;
+
+pub fn verify_ckb_ecdsa(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    inner_verify_btc_ecdsa(pubkey, msg, sig, CKB_LOW_S_POLICY).map(|_| ())
+}
+
+/// [`ChainVerifier`] of CKB, one ECDSA signature per sighash.
+pub struct CkbVerifier;
+
+impl ChainVerifier for CkbVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => btc_signing_hashes(msg),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => verify_ckb_ecdsa(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
 }
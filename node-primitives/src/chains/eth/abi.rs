@@ -0,0 +1,532 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum contract ABI encoding and decoding.
+
+use sp_core::bounded::alloc::{boxed::Box, format, string::{String, ToString}, vec::Vec};
+use sp_core::U256;
+
+const WORD: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiType {
+    Address,
+    Bool,
+    /// `uintN`, N in bits.
+    Uint(usize),
+    /// `intN`, N in bits.
+    Int(usize),
+    /// `bytesN`, N in bytes.
+    FixedBytes(usize),
+    Bytes,
+    String,
+    /// `T[]`
+    Array(Box<AbiType>),
+    /// `T[N]`
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiValue {
+    Address([u8; 20]),
+    Bool(bool),
+    /// Value and width in bits.
+    Uint(U256, usize),
+    /// Two's complement value and width in bits.
+    Int(U256, usize),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AbiValue>),
+    FixedArray(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiType {
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(inner, _) => inner.is_dynamic(),
+            AbiType::Tuple(types) => types.iter().any(|ty| ty.is_dynamic()),
+            _ => false,
+        }
+    }
+
+    /// Canonical type name used in function and event signatures.
+    pub fn signature(&self) -> String {
+        match self {
+            AbiType::Address => "address".to_string(),
+            AbiType::Bool => "bool".to_string(),
+            AbiType::Uint(bits) => format!("uint{bits}"),
+            AbiType::Int(bits) => format!("int{bits}"),
+            AbiType::FixedBytes(len) => format!("bytes{len}"),
+            AbiType::Bytes => "bytes".to_string(),
+            AbiType::String => "string".to_string(),
+            AbiType::Array(inner) => format!("{}[]", inner.signature()),
+            AbiType::FixedArray(inner, len) => format!("{}[{len}]", inner.signature()),
+            AbiType::Tuple(types) => format!("({})", join_signatures(types)),
+        }
+    }
+
+    /// Size of the type in the head of an enclosing sequence.
+    fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return WORD;
+        }
+        match self {
+            AbiType::FixedArray(inner, len) => inner.head_size().saturating_mul(*len),
+            AbiType::Tuple(types) => types.iter().map(|ty| ty.head_size()).sum(),
+            _ => WORD,
+        }
+    }
+}
+
+pub(crate) fn join_signatures(types: &[AbiType]) -> String {
+    types.iter().map(|ty| ty.signature()).collect::<Vec<_>>().join(",")
+}
+
+impl AbiValue {
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiValue::Bytes(_) | AbiValue::String(_) | AbiValue::Array(_) => true,
+            AbiValue::FixedArray(values) | AbiValue::Tuple(values) => values.iter().any(|v| v.is_dynamic()),
+            _ => false,
+        }
+    }
+}
+
+/// ABI encode `values` as a tuple, the layout used for function arguments.
+pub fn abi_encode(values: &[AbiValue]) -> Result<Vec<u8>, String> {
+    encode_sequence(values)
+}
+
+/// ABI decode `data` as a tuple of `types`.
+///
+/// Decoding is strict: every dynamic offset has to point at the end of the previous tail,
+/// padding has to be zero and no bytes may follow the encoding, so any non-canonical
+/// encoding, including overlapping or aliased tails, is rejected.
+pub fn abi_decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, String> {
+    let (values, end) = decode_sequence(types.iter(), data, 0)?;
+    if end != data.len() {
+        return Err(format!("{} trailing bytes after abi encoding", data.len() - end));
+    }
+    Ok(values)
+}
+
+fn encode_sequence(values: &[AbiValue]) -> Result<Vec<u8>, String> {
+    let encoded = values.iter().map(encode_value).collect::<Result<Vec<_>, String>>()?;
+    let heads_len: usize = values
+        .iter()
+        .zip(&encoded)
+        .map(|(v, e)| if v.is_dynamic() { WORD } else { e.len() })
+        .sum();
+    let mut heads = Vec::with_capacity(heads_len);
+    let mut tails = Vec::new();
+    for (value, encoded) in values.iter().zip(encoded) {
+        if value.is_dynamic() {
+            heads.extend_from_slice(&encode_usize(heads_len + tails.len()));
+            tails.extend(encoded);
+        } else {
+            heads.extend(encoded);
+        }
+    }
+    heads.extend(tails);
+    Ok(heads)
+}
+
+fn encode_value(value: &AbiValue) -> Result<Vec<u8>, String> {
+    match value {
+        AbiValue::Address(address) => {
+            let mut word = [0u8; WORD];
+            word[12..].copy_from_slice(address);
+            Ok(word.to_vec())
+        }
+        AbiValue::Bool(b) => Ok(encode_usize(*b as usize).to_vec()),
+        AbiValue::Uint(v, bits) => {
+            check_int_bits(*bits)?;
+            if v.bits() > *bits {
+                return Err(format!("value {v} overflows uint{bits}"));
+            }
+            Ok(encode_u256(*v).to_vec())
+        }
+        AbiValue::Int(v, bits) => {
+            check_int_bits(*bits)?;
+            if !is_sign_extended(*v, *bits) {
+                return Err(format!("value {v} overflows int{bits}"));
+            }
+            Ok(encode_u256(*v).to_vec())
+        }
+        AbiValue::FixedBytes(bytes) => {
+            if bytes.is_empty() || bytes.len() > WORD {
+                return Err(format!("invalid bytes{} value", bytes.len()));
+            }
+            let mut word = [0u8; WORD];
+            word[..bytes.len()].copy_from_slice(bytes);
+            Ok(word.to_vec())
+        }
+        AbiValue::Bytes(bytes) => Ok(encode_packed_bytes(bytes)),
+        AbiValue::String(s) => Ok(encode_packed_bytes(s.as_bytes())),
+        AbiValue::Array(values) => {
+            let mut out = encode_usize(values.len()).to_vec();
+            out.extend(encode_sequence(values)?);
+            Ok(out)
+        }
+        AbiValue::FixedArray(values) | AbiValue::Tuple(values) => encode_sequence(values),
+    }
+}
+
+fn encode_packed_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = encode_usize(bytes.len()).to_vec();
+    out.extend_from_slice(bytes);
+    out.resize(WORD + bytes.len().div_ceil(WORD) * WORD, 0);
+    out
+}
+
+fn encode_u256(v: U256) -> [u8; WORD] {
+    let mut word = [0u8; WORD];
+    v.to_big_endian(&mut word);
+    word
+}
+
+fn encode_usize(v: usize) -> [u8; WORD] {
+    encode_u256(U256::from(v))
+}
+
+fn check_int_bits(bits: usize) -> Result<(), String> {
+    if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+        return Err(format!("invalid integer width: {bits}"));
+    }
+    Ok(())
+}
+
+/// Whether the 256-bit two's complement `v` is the sign extension of its low `bits` bits.
+fn is_sign_extended(v: U256, bits: usize) -> bool {
+    if bits == 256 {
+        return true;
+    }
+    let negative = v.bit(255);
+    (bits - 1..256).all(|i| v.bit(i) == negative)
+}
+
+/// Decode the sequence of `types` whose heads start at `base`, returning the values and
+/// the end of the last tail.
+fn decode_sequence<'a, I>(types: I, data: &[u8], base: usize) -> Result<(Vec<AbiValue>, usize), String>
+where
+    I: Iterator<Item = &'a AbiType> + Clone,
+{
+    let heads_len = types.clone().fold(0usize, |len, ty| len.saturating_add(ty.head_size()));
+    let mut tail = base
+        .checked_add(heads_len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| "abi data too short for heads".to_string())?;
+    let mut values = Vec::new();
+    let mut head = base;
+    for ty in types {
+        if ty.is_dynamic() {
+            let offset = read_usize(data, head)?;
+            if base.checked_add(offset) != Some(tail) {
+                return Err(format!("non-canonical abi offset {offset}"));
+            }
+            let (value, end) = decode_value(ty, data, tail)?;
+            values.push(value);
+            tail = end;
+        } else {
+            values.push(decode_value(ty, data, head)?.0);
+        }
+        head += ty.head_size();
+    }
+    Ok((values, tail))
+}
+
+/// Decode a value encoded at `pos`, returning it and the end of its encoding.
+fn decode_value(ty: &AbiType, data: &[u8], pos: usize) -> Result<(AbiValue, usize), String> {
+    let value = match ty {
+        AbiType::Address => {
+            let word = read_word(data, pos)?;
+            if word[..12].iter().any(|b| *b != 0) {
+                return Err("dirty abi address padding".to_string());
+            }
+            let mut address = [0u8; 20];
+            address.copy_from_slice(&word[12..]);
+            AbiValue::Address(address)
+        }
+        AbiType::Bool => match U256::from_big_endian(read_word(data, pos)?) {
+            v if v.is_zero() => AbiValue::Bool(false),
+            v if v == U256::one() => AbiValue::Bool(true),
+            _ => return Err("invalid abi bool".to_string()),
+        },
+        AbiType::Uint(bits) => {
+            check_int_bits(*bits)?;
+            let v = U256::from_big_endian(read_word(data, pos)?);
+            if v.bits() > *bits {
+                return Err(format!("abi value overflows uint{bits}"));
+            }
+            AbiValue::Uint(v, *bits)
+        }
+        AbiType::Int(bits) => {
+            check_int_bits(*bits)?;
+            let v = U256::from_big_endian(read_word(data, pos)?);
+            if !is_sign_extended(v, *bits) {
+                return Err(format!("abi value overflows int{bits}"));
+            }
+            AbiValue::Int(v, *bits)
+        }
+        AbiType::FixedBytes(len) => {
+            if *len == 0 || *len > WORD {
+                return Err(format!("invalid abi type bytes{len}"));
+            }
+            let word = read_word(data, pos)?;
+            if word[*len..].iter().any(|b| *b != 0) {
+                return Err(format!("dirty abi bytes{len} padding"));
+            }
+            AbiValue::FixedBytes(word[..*len].to_vec())
+        }
+        AbiType::Bytes => return decode_packed_bytes(data, pos).map(|(bytes, end)| (AbiValue::Bytes(bytes), end)),
+        AbiType::String => {
+            let (bytes, end) = decode_packed_bytes(data, pos)?;
+            let s = String::from_utf8(bytes).map_err(|_| "invalid abi utf8 string".to_string())?;
+            return Ok((AbiValue::String(s), end));
+        }
+        AbiType::Array(inner) => {
+            let len = read_usize(data, pos)?;
+            let start = pos + WORD;
+            // bounds the element count by the input size, zero sized elements included
+            if len > data.len() - start {
+                return Err(format!("abi array length {len} out of bounds"));
+            }
+            let (values, end) = decode_sequence(core::iter::repeat_n(inner.as_ref(), len), data, start)?;
+            return Ok((AbiValue::Array(values), end));
+        }
+        AbiType::FixedArray(inner, len) => {
+            if *len > data.len().saturating_sub(pos) {
+                return Err(format!("abi array length {len} out of bounds"));
+            }
+            let (values, end) = decode_sequence(core::iter::repeat_n(inner.as_ref(), *len), data, pos)?;
+            return Ok((AbiValue::FixedArray(values), end));
+        }
+        AbiType::Tuple(types) => {
+            let (values, end) = decode_sequence(types.iter(), data, pos)?;
+            return Ok((AbiValue::Tuple(values), end));
+        }
+    };
+    Ok((value, pos + WORD))
+}
+
+fn decode_packed_bytes(data: &[u8], pos: usize) -> Result<(Vec<u8>, usize), String> {
+    let len = read_usize(data, pos)?;
+    let start = pos + WORD;
+    let padded_len = len.div_ceil(WORD) * WORD;
+    if padded_len > data.len() - start {
+        return Err(format!("abi bytes length {len} out of bounds"));
+    }
+    if data[start + len..start + padded_len].iter().any(|b| *b != 0) {
+        return Err("dirty abi bytes padding".to_string());
+    }
+    Ok((data[start..start + len].to_vec(), start + padded_len))
+}
+
+fn read_word(data: &[u8], pos: usize) -> Result<&[u8], String> {
+    data.get(pos..pos.saturating_add(WORD))
+        .filter(|word| word.len() == WORD)
+        .ok_or_else(|| format!("abi data too short to read word at {pos}"))
+}
+
+fn read_usize(data: &[u8], pos: usize) -> Result<usize, String> {
+    let v = U256::from_big_endian(read_word(data, pos)?);
+    if v > U256::from(u32::MAX) {
+        return Err(format!("abi offset or length {v} too large"));
+    }
+    Ok(v.as_usize())
+}
+
+/// Encode a function call: the 4-byte selector followed by the ABI encoded arguments.
+pub fn abi_encode_call(selector: [u8; 4], args: &[AbiValue]) -> Result<Vec<u8>, String> {
+    let mut out = selector.to_vec();
+    out.extend(abi_encode(args)?);
+    Ok(out)
+}
+
+/// Function selector of a canonical signature such as `transfer(address,uint256)`.
+pub fn abi_function_selector(signature: &str) -> [u8; 4] {
+    let hash = sp_io::hashing::keccak_256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+pub(crate) fn abi_address(bytes: &[u8], name: &str) -> Result<[u8; 20], String> {
+    let mut address = [0u8; 20];
+    match bytes.len() {
+        20 => address.copy_from_slice(bytes),
+        WORD if bytes[..12].iter().all(|b| *b == 0) => address.copy_from_slice(&bytes[12..]),
+        len => return Err(format!("invalid {name} address length: {len:?}")),
+    }
+    Ok(address)
+}
+
+pub(crate) fn abi_uint256(bytes: &[u8], name: &str) -> Result<U256, String> {
+    if bytes.len() != WORD {
+        return Err(format!("invalid {name} uint256 length: {:?}", bytes.len()));
+    }
+    Ok(U256::from_big_endian(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<u8> {
+        words.iter().flat_map(|w| hex::decode(format!("{w:0>64}")).unwrap()).collect()
+    }
+
+    fn uint(v: u64) -> AbiValue {
+        AbiValue::Uint(v.into(), 256)
+    }
+
+    // Solidity ABI specification, `f(uint256,uint32[],bytes10,bytes)`
+    fn spec_example() -> (Vec<AbiType>, Vec<AbiValue>, Vec<u8>) {
+        let types = vec![
+            AbiType::Uint(256),
+            AbiType::Array(Box::new(AbiType::Uint(32))),
+            AbiType::FixedBytes(10),
+            AbiType::Bytes,
+        ];
+        let values = vec![
+            uint(0x123),
+            AbiValue::Array(vec![AbiValue::Uint(0x456.into(), 32), AbiValue::Uint(0x789.into(), 32)]),
+            AbiValue::FixedBytes(b"1234567890".to_vec()),
+            AbiValue::Bytes(b"Hello, world!".to_vec()),
+        ];
+        let encoded = words(&[
+            "123",
+            "80",
+            "3132333435363738393000000000000000000000000000000000000000000000",
+            "e0",
+            "2",
+            "456",
+            "789",
+            "d",
+            "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+        ]);
+        (types, values, encoded)
+    }
+
+    #[test]
+    fn spec_example_round_trips() {
+        let (types, values, encoded) = spec_example();
+        let signature = format!("f({})", join_signatures(&types));
+        assert_eq!(signature, "f(uint256,uint32[],bytes10,bytes)");
+        assert_eq!(abi_function_selector(&signature), [0x8b, 0xe6, 0x52, 0x46]);
+        assert_eq!(abi_encode(&values).unwrap(), encoded);
+        assert_eq!(abi_decode(&types, &encoded).unwrap(), values);
+        assert_eq!(abi_encode_call([0x8b, 0xe6, 0x52, 0x46], &values).unwrap()[4..], encoded);
+    }
+
+    #[test]
+    fn nested_values_round_trip() {
+        let types = vec![AbiType::Tuple(vec![
+            AbiType::Array(Box::new(AbiType::Array(Box::new(AbiType::Uint(256))))),
+            AbiType::String,
+            AbiType::FixedArray(Box::new(AbiType::Bytes), 2),
+            AbiType::FixedArray(Box::new(AbiType::Address), 2),
+            AbiType::Int(8),
+            AbiType::Bool,
+        ])];
+        let values = vec![AbiValue::Tuple(vec![
+            AbiValue::Array(vec![AbiValue::Array(vec![uint(1), uint(2)]), AbiValue::Array(vec![uint(3)])]),
+            AbiValue::String("three".into()),
+            AbiValue::FixedArray(vec![AbiValue::Bytes(vec![]), AbiValue::Bytes(vec![0xab; 33])]),
+            AbiValue::FixedArray(vec![AbiValue::Address([1; 20]), AbiValue::Address([2; 20])]),
+            AbiValue::Int(U256::MAX, 8),
+            AbiValue::Bool(true),
+        ])];
+        assert!(types[0].is_dynamic());
+        assert_eq!(types[0].signature(), "(uint256[][],string,bytes[2],address[2],int8,bool)");
+        let encoded = abi_encode(&values).unwrap();
+        assert_eq!(abi_decode(&types, &encoded).unwrap(), values);
+    }
+
+    #[test]
+    fn rejects_dirty_padding_and_overflow() {
+        let cases = [
+            (AbiType::Address, words(&["100000000000000000000000000000000000000000"])),
+            (AbiType::Bool, words(&["2"])),
+            (AbiType::Uint(8), words(&["100"])),
+            (AbiType::Int(8), words(&["80"])),
+            (AbiType::FixedBytes(1), words(&["0101000000000000000000000000000000000000000000000000000000000000"])),
+            (AbiType::Bytes, words(&["20", "1", "01"])),
+            (AbiType::String, words(&["20", "1", "ff00000000000000000000000000000000000000000000000000000000000000"])),
+        ];
+        for (ty, data) in cases {
+            let data = if ty.is_dynamic() { data } else { data[..32].to_vec() };
+            assert!(abi_decode(core::slice::from_ref(&ty), &data).is_err(), "{ty:?}");
+        }
+        assert!(abi_encode(&[AbiValue::Uint(256.into(), 8)]).is_err());
+        assert!(abi_encode(&[AbiValue::Int(128.into(), 8)]).is_err());
+        assert!(abi_encode(&[AbiValue::FixedBytes(vec![0; 33])]).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_offsets_and_lengths() {
+        let bytes = [AbiType::Bytes];
+        assert!(abi_decode(&bytes, &words(&["40", "0"])).is_err());
+        assert!(abi_decode(&bytes, &words(&["ffffffffffffffff", "0"])).is_err());
+        assert!(abi_decode(&bytes, &words(&["20", "21", "0"])).is_err());
+        assert!(abi_decode(&bytes, &words(&["20"])).is_err());
+        let array = [AbiType::Array(Box::new(AbiType::Tuple(vec![])))];
+        assert!(abi_decode(&array, &words(&["20", "ffffffff"])).is_err());
+        assert!(abi_decode(&[AbiType::FixedArray(Box::new(AbiType::Tuple(vec![])), usize::MAX)], &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_non_canonical_encodings() {
+        let (types, _, encoded) = spec_example();
+        // trailing bytes
+        assert!(abi_decode(&types, &[&encoded[..], &[0u8; 32]].concat()).is_err());
+        assert!(abi_decode(&types[..1], &encoded).is_err());
+
+        let pair = [AbiType::Bytes, AbiType::Bytes];
+        assert_eq!(
+            abi_decode(&pair, &words(&["40", "60", "0", "0"])).unwrap(),
+            vec![AbiValue::Bytes(vec![]), AbiValue::Bytes(vec![])],
+        );
+        // both offsets alias one tail
+        assert!(abi_decode(&pair, &words(&["40", "40", "0"])).is_err());
+        // tails out of order or with a gap
+        assert!(abi_decode(&pair, &words(&["60", "40", "0", "0"])).is_err());
+        assert!(abi_decode(&pair, &words(&["60", "80", "0", "0", "0"])).is_err());
+
+        // uint256[][] whose inner arrays all point at one tail
+        let nested = [AbiType::Array(Box::new(AbiType::Array(Box::new(AbiType::Uint(256)))))];
+        let aliased = words(&["20", "3", "60", "60", "60", "1", "7"]);
+        assert!(abi_decode(&nested, &aliased).is_err());
+        let canonical = words(&["20", "3", "60", "a0", "e0", "1", "7", "1", "7", "1", "7"]);
+        assert_eq!(
+            abi_decode(&nested, &canonical).unwrap(),
+            vec![AbiValue::Array(vec![AbiValue::Array(vec![uint(7)]); 3])],
+        );
+    }
+
+    #[test]
+    fn address_and_uint_helpers() {
+        assert_eq!(abi_address(&[7; 20], "test"), Ok([7; 20]));
+        assert_eq!(abi_address(&words(&["0707070707070707070707070707070707070707"]), "test"), Ok([7; 20]));
+        assert!(abi_address(&[1; 32], "test").is_err());
+        assert!(abi_address(&[7; 21], "test").is_err());
+        assert_eq!(abi_uint256(&words(&["2a"]), "test"), Ok(42.into()));
+        assert!(abi_uint256(&[1; 31], "test").is_err());
+    }
+}
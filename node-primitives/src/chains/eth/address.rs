@@ -0,0 +1,172 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{format, string::{String, ToString}};
+use super::{to_eth_signed_message_hash, ETH_LOW_S_POLICY};
+use crate::{inner_ecdsa_recover_pubkey_with_policy, AccountId20, Hash256, PubkeyFormat};
+
+/// Ethereum address of a secp256k1 public key, compressed (33 bytes), uncompressed
+/// (65 bytes) or raw (64 bytes).
+pub fn eth_address_from_pubkey(pubkey: &[u8]) -> Result<[u8; 20], String> {
+    let pk = secp256k1::PublicKey::parse_slice(pubkey, None)
+        .map_err(|e| format!("invalid secp256k1 public key: {e:?}"))?;
+    let hash = sp_io::hashing::keccak_256(&pk.serialize()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Ok(address)
+}
+
+/// Account id of a secp256k1 public key on an EVM compatible runtime, its Ethereum address.
+pub fn eth_account_id_from_pubkey(pubkey: &[u8]) -> Result<AccountId20, String> {
+    eth_address_from_pubkey(pubkey).map(AccountId20::from)
+}
+
+/// Format an address with its EIP-55 mixed-case checksum, `0x` prefixed.
+pub fn eth_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = sp_io::hashing::keccak_256(lower.as_bytes());
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+        if nibble >= 8 {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Parse a hex address, `0x` prefix optional.
+///
+/// Mixed-case input must carry a valid EIP-55 checksum; all lower or all upper case
+/// input carries no checksum and is accepted as is.
+pub fn eth_parse_checksum_address(s: &str) -> Result<[u8; 20], String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    if digits.len() != 40 {
+        return Err(format!("invalid eth address length: {:?}", digits.len()));
+    }
+    let bytes = hex::decode(digits).map_err(|e| e.to_string())?;
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&bytes);
+    let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && eth_checksum_address(&address)[2..] != *digits {
+        return Err("invalid eip55 address checksum".to_string());
+    }
+    Ok(address)
+}
+
+/// Recover the signer address of a 65-byte `r || s || v` signature.
+///
+/// `msg` is hashed with keccak256 and wrapped with the Ethereum signed message prefix,
+/// the same digest checked by `eth_ecdsa_verify` with `Hash256::Keccak256`, and high-S
+/// signatures are rejected as there.
+pub fn eth_ecdsa_recover_address(msg: &[u8], sig: &[u8]) -> Result<[u8; 20], String> {
+    let pubkey = inner_ecdsa_recover_pubkey_with_policy(
+        msg,
+        sig,
+        Some(Hash256::Keccak256),
+        to_eth_signed_message_hash,
+        PubkeyFormat::Uncompressed,
+        ETH_LOW_S_POLICY,
+    )
+    .map_err(|e| format!("recover eth signer failed: {e}"))?;
+    eth_address_from_pubkey(&pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // EIP-55 test cases, all caps, all lower and mixed case
+    const EIP55: [&str; 8] = [
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn eip55_checksums() {
+        for s in EIP55 {
+            let address = eth_parse_checksum_address(s).unwrap();
+            assert_eq!(eth_checksum_address(&address), s);
+            assert_eq!(eth_parse_checksum_address(&s[2..]), Ok(address));
+            assert_eq!(eth_parse_checksum_address(&s.to_lowercase()), Ok(address));
+            assert_eq!(eth_parse_checksum_address(&format!("0x{}", s[2..].to_uppercase())), Ok(address));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_checksums_and_lengths() {
+        // flip the case of one letter of a mixed-case address
+        assert!(eth_parse_checksum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+        assert!(eth_parse_checksum_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(eth_parse_checksum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+        assert!(eth_parse_checksum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAedd").is_err());
+        assert!(eth_parse_checksum_address("0xgaAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+    }
+
+    #[test]
+    fn address_of_every_pubkey_form() {
+        // secret key [0x46; 32], the sender of the EIP-155 example transaction
+        let sk = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+        let pk = secp256k1::PublicKey::from_secret_key(&sk);
+        let expected = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F";
+        let uncompressed = pk.serialize();
+        for pubkey in [&pk.serialize_compressed()[..], &uncompressed[..], &uncompressed[1..]] {
+            let address = eth_address_from_pubkey(pubkey).unwrap();
+            assert_eq!(eth_checksum_address(&address), expected);
+            assert_eq!(eth_account_id_from_pubkey(pubkey).unwrap(), AccountId20::from(address));
+        }
+        assert!(eth_address_from_pubkey(&uncompressed[..33]).is_err());
+        let mut off_curve = [0xff; 33];
+        off_curve[0] = 0x02;
+        assert!(eth_address_from_pubkey(&off_curve).is_err());
+    }
+
+    #[test]
+    fn recovers_signer_address() {
+        let sk = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+        let msg = b"recover eth signer";
+        let digest = to_eth_signed_message_hash(sp_io::hashing::keccak_256(msg).to_vec());
+        let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse_slice(&digest).unwrap(), &sk);
+        let mut sig = sig.serialize().to_vec();
+        sig.push(27 + recovery_id.serialize());
+
+        let address = eth_ecdsa_recover_address(msg, &sig).unwrap();
+        assert_eq!(eth_checksum_address(&address), "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F");
+        assert_ne!(eth_ecdsa_recover_address(b"another message", &sig), Ok(address));
+        assert!(eth_ecdsa_recover_address(msg, &sig[..64]).is_err());
+
+        // the high-S form of the same signature, refused like `eth_ecdsa_verify` does
+        let mut high_s = secp256k1::Signature::parse_slice(&sig[..64]).unwrap();
+        high_s.s = -high_s.s;
+        let mut high_s = high_s.serialize().to_vec();
+        high_s.push(55 - sig[64]);
+        assert_eq!(
+            eth_ecdsa_recover_address(msg, &high_s),
+            Err(format!("recover eth signer failed: {}", crate::VerifyError::HighS)),
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! EIP-1271 contract wallet signatures and EIP-6492 counterfactual wrappers.
+
+use sp_core::bounded::alloc::{format, string::{String, ToString}, vec::Vec};
+use super::abi::{abi_decode, abi_encode, abi_encode_call, AbiType, AbiValue};
+
+/// Selector of `isValidSignature(bytes32,bytes)`, also the value returned on success.
+pub const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Suffix marking an EIP-6492 wrapped signature.
+pub const EIP6492_MAGIC_SUFFIX: [u8; 32] = [
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+    0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92, 0x64, 0x92,
+];
+
+/// Signature of a not yet deployed contract wallet, with the factory call deploying it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Eip6492Signature {
+    pub factory: [u8; 20],
+    pub factory_calldata: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Read-only access to an EVM, e.g. an RPC client or a local EVM stand-in.
+///
+/// Calls are simulations in the manner of `eth_call`, state changes never outlive the call
+/// making them.
+pub trait EvmCaller {
+    /// Execute a call against `to` and return its output.
+    fn call(&mut self, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, String>;
+
+    /// Execute the call `prepare_data` against `prepare_to`, then the call against `to` on
+    /// top of its state changes, all in one simulation, and return the output of the latter.
+    ///
+    /// Fails when the preparing call reverts. Over RPC this is a bundle simulation such as
+    /// `eth_callMany`, two separate `eth_call`s lose the state of the first one.
+    fn call_after(
+        &mut self,
+        prepare_to: [u8; 20],
+        prepare_data: &[u8],
+        to: [u8; 20],
+        data: &[u8],
+    ) -> Result<Vec<u8>, String>;
+
+    /// Whether `address` has contract code.
+    fn is_deployed(&mut self, address: [u8; 20]) -> Result<bool, String>;
+}
+
+/// Calldata of `isValidSignature(hash, signature)`.
+pub fn eip1271_is_valid_signature_calldata(hash: &[u8; 32], signature: &[u8]) -> Result<Vec<u8>, String> {
+    abi_encode_call(
+        EIP1271_MAGIC_VALUE,
+        &[AbiValue::FixedBytes(hash.to_vec()), AbiValue::Bytes(signature.to_vec())],
+    )
+}
+
+/// Decode the `bytes4` output of `isValidSignature`, true only for the magic value.
+pub fn eip1271_decode_is_valid_signature_result(output: &[u8]) -> Result<bool, String> {
+    if output.len() != 32 {
+        return Err(format!("invalid isValidSignature output length: {:?}", output.len()));
+    }
+    match abi_decode(&[AbiType::FixedBytes(4)], output)?.as_slice() {
+        [AbiValue::FixedBytes(value)] => Ok(value[..] == EIP1271_MAGIC_VALUE),
+        _ => Err("invalid isValidSignature output".to_string()),
+    }
+}
+
+/// Split an EIP-6492 wrapped signature, `None` when `sig` is not wrapped.
+pub fn eip6492_unwrap_signature(sig: &[u8]) -> Result<Option<Eip6492Signature>, String> {
+    let Some(wrapped) = sig.strip_suffix(&EIP6492_MAGIC_SUFFIX[..]) else {
+        return Ok(None);
+    };
+    match abi_decode(&[AbiType::Address, AbiType::Bytes, AbiType::Bytes], wrapped)?.as_slice() {
+        [AbiValue::Address(factory), AbiValue::Bytes(factory_calldata), AbiValue::Bytes(signature)] => {
+            Ok(Some(Eip6492Signature {
+                factory: *factory,
+                factory_calldata: factory_calldata.clone(),
+                signature: signature.clone(),
+            }))
+        }
+        _ => Err("invalid eip6492 signature".to_string()),
+    }
+}
+
+/// Wrap a counterfactual wallet signature, the inverse of [`eip6492_unwrap_signature`].
+pub fn eip6492_wrap_signature(wrapped: &Eip6492Signature) -> Result<Vec<u8>, String> {
+    let mut out = abi_encode(&[
+        AbiValue::Address(wrapped.factory),
+        AbiValue::Bytes(wrapped.factory_calldata.clone()),
+        AbiValue::Bytes(wrapped.signature.clone()),
+    ])?;
+    out.extend_from_slice(&EIP6492_MAGIC_SUFFIX);
+    Ok(out)
+}
+
+/// Check a contract wallet signature over `hash` through `isValidSignature`.
+///
+/// EIP-6492 wrapped signatures of a wallet without code yet are checked against the wallet
+/// its factory call deploys, simulated with [`EvmCaller::call_after`].
+pub fn eip1271_verify_signature<C: EvmCaller>(
+    caller: &mut C,
+    wallet: [u8; 20],
+    hash: &[u8; 32],
+    sig: &[u8],
+) -> Result<bool, String> {
+    let output = match eip6492_unwrap_signature(sig)? {
+        Some(wrapped) if !caller.is_deployed(wallet)? => {
+            let calldata = eip1271_is_valid_signature_calldata(hash, &wrapped.signature)?;
+            caller.call_after(wrapped.factory, &wrapped.factory_calldata, wallet, &calldata)?
+        }
+        Some(wrapped) => caller.call(wallet, &eip1271_is_valid_signature_calldata(hash, &wrapped.signature)?)?,
+        None => caller.call(wallet, &eip1271_is_valid_signature_calldata(hash, sig)?)?,
+    };
+    eip1271_decode_is_valid_signature_result(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::abi_function_selector;
+
+    const FACTORY: [u8; 20] = [0xfa; 20];
+    const WALLET: [u8; 20] = [0x5a; 20];
+
+    /// Stand-in EVM with `eth_call` semantics: the factory deploys `WALLET`, whose
+    /// `isValidSignature` accepts the signature `good`, and every call runs on a copy of
+    /// the committed state.
+    struct SimulatedEvm {
+        deployed: Vec<[u8; 20]>,
+    }
+
+    impl SimulatedEvm {
+        fn execute(deployed: &mut Vec<[u8; 20]>, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, String> {
+            if to == FACTORY {
+                deployed.push(WALLET);
+                return Ok(vec![]);
+            }
+            if !deployed.contains(&to) {
+                // calls to an address without code succeed with empty output
+                return Ok(vec![]);
+            }
+            assert_eq!(data[..4], EIP1271_MAGIC_VALUE);
+            let args = abi_decode(&[AbiType::FixedBytes(32), AbiType::Bytes], &data[4..])?;
+            let mut output = [0u8; 32];
+            if args[1] == AbiValue::Bytes(b"good".to_vec()) {
+                output[..4].copy_from_slice(&EIP1271_MAGIC_VALUE);
+            }
+            Ok(output.to_vec())
+        }
+    }
+
+    impl EvmCaller for SimulatedEvm {
+        fn call(&mut self, to: [u8; 20], data: &[u8]) -> Result<Vec<u8>, String> {
+            Self::execute(&mut self.deployed.clone(), to, data)
+        }
+
+        fn call_after(
+            &mut self,
+            prepare_to: [u8; 20],
+            prepare_data: &[u8],
+            to: [u8; 20],
+            data: &[u8],
+        ) -> Result<Vec<u8>, String> {
+            let mut state = self.deployed.clone();
+            Self::execute(&mut state, prepare_to, prepare_data)?;
+            Self::execute(&mut state, to, data)
+        }
+
+        fn is_deployed(&mut self, address: [u8; 20]) -> Result<bool, String> {
+            Ok(self.deployed.contains(&address))
+        }
+    }
+
+    fn wrapped(signature: &[u8]) -> Vec<u8> {
+        eip6492_wrap_signature(&Eip6492Signature {
+            factory: FACTORY,
+            factory_calldata: vec![0xde, 0xad],
+            signature: signature.to_vec(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn is_valid_signature_calldata_and_result() {
+        assert_eq!(abi_function_selector("isValidSignature(bytes32,bytes)"), EIP1271_MAGIC_VALUE);
+        let calldata = eip1271_is_valid_signature_calldata(&[0x11; 32], b"good").unwrap();
+        let expected = [
+            "1626ba7e",
+            "1111111111111111111111111111111111111111111111111111111111111111",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "676f6f6400000000000000000000000000000000000000000000000000000000",
+        ]
+        .concat();
+        assert_eq!(hex::encode(calldata), expected);
+
+        let mut output = [0u8; 32];
+        output[..4].copy_from_slice(&EIP1271_MAGIC_VALUE);
+        assert_eq!(eip1271_decode_is_valid_signature_result(&output), Ok(true));
+        assert_eq!(eip1271_decode_is_valid_signature_result(&[0u8; 32]), Ok(false));
+        // dirty bytes4 padding
+        assert!(eip1271_decode_is_valid_signature_result(&[0xff; 32]).is_err());
+        assert!(eip1271_decode_is_valid_signature_result(&output[..4]).is_err());
+        assert!(eip1271_decode_is_valid_signature_result(&[]).is_err());
+    }
+
+    #[test]
+    fn eip6492_wrap_round_trip() {
+        let sig = wrapped(b"good");
+        assert_eq!(sig[sig.len() - 32..], EIP6492_MAGIC_SUFFIX);
+        let unwrapped = Eip6492Signature {
+            factory: FACTORY,
+            factory_calldata: vec![0xde, 0xad],
+            signature: b"good".to_vec(),
+        };
+        assert_eq!(eip6492_unwrap_signature(&sig), Ok(Some(unwrapped)));
+        assert_eq!(eip6492_unwrap_signature(b"good"), Ok(None));
+        assert_eq!(eip6492_unwrap_signature(&sig[..sig.len() - 1]), Ok(None));
+        assert!(eip6492_unwrap_signature(&sig[32..]).is_err());
+    }
+
+    #[test]
+    fn verifies_deployed_and_counterfactual_wallets() {
+        let mut evm = SimulatedEvm { deployed: vec![] };
+        // an undeployed wallet returns no output
+        assert!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], b"good").is_err());
+        assert_eq!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], &wrapped(b"good")), Ok(true));
+        assert_eq!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], &wrapped(b"bad")), Ok(false));
+        // the deployment only happened within the simulation
+        assert!(!evm.is_deployed(WALLET).unwrap());
+
+        let mut evm = SimulatedEvm { deployed: vec![WALLET] };
+        assert_eq!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], b"good"), Ok(true));
+        assert_eq!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], b"bad"), Ok(false));
+        assert_eq!(eip1271_verify_signature(&mut evm, WALLET, &[0; 32], &wrapped(b"good")), Ok(true));
+    }
+}
@@ -0,0 +1,323 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed decoding of EVM event logs.
+//!
+//! Only the standard ERC-20 events are built in, contract specific events such as the bridge's
+//! are described with [`EthEvent::new`] from the deployed contract's ABI.
+
+use sp_core::bounded::alloc::{format, string::{String, ToString}, vec::Vec};
+use sp_core::U256;
+use super::abi::{abi_decode, join_signatures, AbiType, AbiValue};
+use crate::keccak_256;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthLog {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthEventParam {
+    pub kind: AbiType,
+    pub indexed: bool,
+}
+
+/// Non-anonymous event description, matched on topic0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthEvent {
+    pub name: String,
+    pub params: Vec<EthEventParam>,
+}
+
+/// topic0 of an event: keccak256 of its canonical signature, e.g. `Transfer(address,address,uint256)`.
+pub fn eth_event_topic(signature: &str) -> [u8; 32] {
+    let mut topic = [0u8; 32];
+    topic.copy_from_slice(&keccak_256(signature.as_bytes()));
+    topic
+}
+
+impl EthEvent {
+    pub fn new(name: &str, params: &[(AbiType, bool)]) -> Self {
+        EthEvent {
+            name: name.to_string(),
+            params: params.iter().map(|(kind, indexed)| EthEventParam { kind: kind.clone(), indexed: *indexed }).collect(),
+        }
+    }
+
+    pub fn signature(&self) -> String {
+        let types: Vec<AbiType> = self.params.iter().map(|p| p.kind.clone()).collect();
+        format!("{}({})", self.name, join_signatures(&types))
+    }
+
+    pub fn topic(&self) -> [u8; 32] {
+        eth_event_topic(&self.signature())
+    }
+
+    /// Decode the parameters of `log` in declaration order.
+    ///
+    /// Indexed parameters other than value types (bytes, string, arrays and tuples, static
+    /// or not) are only present as the keccak256 hash of their encoding and decode to a
+    /// `bytes32` value.
+    pub fn decode(&self, log: &EthLog) -> Result<Vec<AbiValue>, String> {
+        let indexed_num = self.params.iter().filter(|p| p.indexed).count();
+        if log.topics.len() != indexed_num + 1 {
+            return Err(format!(
+                "invalid topic num {} for event {}, expect {}",
+                log.topics.len(),
+                self.name,
+                indexed_num + 1
+            ));
+        }
+        if log.topics[0] != self.topic() {
+            return Err(format!("log topic0 does not match event {}", self.name));
+        }
+        let data_types: Vec<AbiType> = self.params.iter().filter(|p| !p.indexed).map(|p| p.kind.clone()).collect();
+        let mut data_values = abi_decode(&data_types, &log.data)?.into_iter();
+        let mut topics = log.topics[1..].iter();
+        let mut values = Vec::with_capacity(self.params.len());
+        for param in &self.params {
+            let value = if param.indexed {
+                let topic = topics.next().ok_or_else(|| "missing event topic".to_string())?;
+                if is_hashed_in_topic(&param.kind) {
+                    AbiValue::FixedBytes(topic.to_vec())
+                } else {
+                    abi_decode(core::slice::from_ref(&param.kind), topic)?.remove(0)
+                }
+            } else {
+                data_values.next().ok_or_else(|| "missing event data value".to_string())?
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+/// Whether an indexed parameter of type `kind` is stored in its topic as a hash.
+fn is_hashed_in_topic(kind: &AbiType) -> bool {
+    matches!(kind, AbiType::FixedArray(..) | AbiType::Tuple(_)) || kind.is_dynamic()
+}
+
+/// `Transfer(address indexed from, address indexed to, uint256 value)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc20Transfer {
+    pub token: [u8; 20],
+    pub from: [u8; 20],
+    pub to: [u8; 20],
+    pub value: U256,
+}
+
+/// `Approval(address indexed owner, address indexed spender, uint256 value)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc20Approval {
+    pub token: [u8; 20],
+    pub owner: [u8; 20],
+    pub spender: [u8; 20],
+    pub value: U256,
+}
+
+pub fn erc20_transfer_event() -> EthEvent {
+    EthEvent::new("Transfer", &[(AbiType::Address, true), (AbiType::Address, true), (AbiType::Uint(256), false)])
+}
+
+pub fn erc20_approval_event() -> EthEvent {
+    EthEvent::new("Approval", &[(AbiType::Address, true), (AbiType::Address, true), (AbiType::Uint(256), false)])
+}
+
+/// Decode an ERC-20 `Transfer` log, ERC-721 transfers (indexed token id) are rejected.
+pub fn decode_erc20_transfer(log: &EthLog) -> Result<Erc20Transfer, String> {
+    match erc20_transfer_event().decode(log)?.as_slice() {
+        [AbiValue::Address(from), AbiValue::Address(to), AbiValue::Uint(value, _)] => Ok(Erc20Transfer {
+            token: log.address,
+            from: *from,
+            to: *to,
+            value: *value,
+        }),
+        _ => Err("invalid erc20 transfer log".to_string()),
+    }
+}
+
+pub fn decode_erc20_approval(log: &EthLog) -> Result<Erc20Approval, String> {
+    match erc20_approval_event().decode(log)?.as_slice() {
+        [AbiValue::Address(owner), AbiValue::Address(spender), AbiValue::Uint(value, _)] => Ok(Erc20Approval {
+            token: log.address,
+            owner: *owner,
+            spender: *spender,
+            value: *value,
+        }),
+        _ => Err("invalid erc20 approval log".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::abi_encode;
+
+    const TOKEN: [u8; 20] = [0xee; 20];
+    const ALICE: [u8; 20] = [0xa1; 20];
+    const BOB: [u8; 20] = [0xb0; 20];
+
+    fn word(bytes: &[u8]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[32 - bytes.len()..].copy_from_slice(bytes);
+        word
+    }
+
+    fn topic0(hex: &str) -> [u8; 32] {
+        let mut topic = [0u8; 32];
+        topic.copy_from_slice(&hex::decode(hex).unwrap());
+        topic
+    }
+
+    fn transfer_log() -> EthLog {
+        EthLog {
+            address: TOKEN,
+            topics: vec![
+                topic0("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"),
+                word(&ALICE),
+                word(&BOB),
+            ],
+            data: word(&1_000_000u64.to_be_bytes()).to_vec(),
+        }
+    }
+
+    #[test]
+    fn erc20_transfer_and_approval() {
+        assert_eq!(
+            decode_erc20_transfer(&transfer_log()),
+            Ok(Erc20Transfer { token: TOKEN, from: ALICE, to: BOB, value: 1_000_000.into() }),
+        );
+
+        let approval = EthLog {
+            topics: vec![
+                topic0("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925"),
+                word(&ALICE),
+                word(&BOB),
+            ],
+            data: [0xff; 32].to_vec(),
+            ..transfer_log()
+        };
+        assert_eq!(
+            decode_erc20_approval(&approval),
+            Ok(Erc20Approval { token: TOKEN, owner: ALICE, spender: BOB, value: U256::MAX }),
+        );
+        assert!(decode_erc20_transfer(&approval).is_err());
+        assert!(decode_erc20_approval(&transfer_log()).is_err());
+    }
+
+    #[test]
+    fn caller_defined_event() {
+        let event = EthEvent::new(
+            "Deposit",
+            &[
+                (AbiType::Address, true),
+                (AbiType::Address, true),
+                (AbiType::Uint(256), false),
+                (AbiType::Uint(32), false),
+                (AbiType::Bytes, false),
+            ],
+        );
+        assert_eq!(event.signature(), "Deposit(address,address,uint256,uint32,bytes)");
+        let log = EthLog {
+            address: [0xbb; 20],
+            topics: vec![
+                topic0("824df53e9dc185589c7536e3375ce23509aea5096aab84ba42e7802b60911347"),
+                word(&ALICE),
+                word(&TOKEN),
+            ],
+            data: hex::decode(
+                [
+                    "00000000000000000000000000000000000000000000000000000000000003e8",
+                    "0000000000000000000000000000000000000000000000000000000000000038",
+                    "0000000000000000000000000000000000000000000000000000000000000060",
+                    "0000000000000000000000000000000000000000000000000000000000000004",
+                    "6263317100000000000000000000000000000000000000000000000000000000",
+                ]
+                .concat(),
+            )
+            .unwrap(),
+        };
+        assert_eq!(
+            event.decode(&log),
+            Ok(vec![
+                AbiValue::Address(ALICE),
+                AbiValue::Address(TOKEN),
+                AbiValue::Uint(1000.into(), 256),
+                AbiValue::Uint(56.into(), 32),
+                AbiValue::Bytes(b"bc1q".to_vec()),
+            ]),
+        );
+        assert!(event.decode(&transfer_log()).is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_topics() {
+        // ERC-721 Transfer has the same topic0 and an indexed token id
+        let mut nft = transfer_log();
+        nft.topics.push(word(&[0x05]));
+        nft.data.clear();
+        assert!(decode_erc20_transfer(&nft).is_err());
+
+        let mut missing = transfer_log();
+        missing.topics.pop();
+        assert!(decode_erc20_transfer(&missing).is_err());
+
+        let mut other = transfer_log();
+        other.topics[0] = [0x01; 32];
+        assert!(decode_erc20_transfer(&other).is_err());
+
+        let mut dirty = transfer_log();
+        dirty.topics[1][0] = 1;
+        assert!(decode_erc20_transfer(&dirty).is_err());
+
+        let mut trailing = transfer_log();
+        trailing.data.extend([0u8; 32]);
+        assert!(decode_erc20_transfer(&trailing).is_err());
+    }
+
+    #[test]
+    fn indexed_reference_types_are_hashed() {
+        let event = EthEvent::new(
+            "Pair",
+            &[
+                (AbiType::FixedArray(Box::new(AbiType::Uint(256)), 2), true),
+                (AbiType::Tuple(vec![AbiType::Uint(8), AbiType::Bool]), true),
+                (AbiType::String, true),
+                (AbiType::Uint(8), false),
+            ],
+        );
+        assert_eq!(event.signature(), "Pair(uint256[2],(uint8,bool),string,uint8)");
+        let pair = [AbiValue::FixedArray(vec![AbiValue::Uint(1.into(), 256), AbiValue::Uint(2.into(), 256)])];
+        let pair_hash = topic0(&hex::encode(keccak_256(&abi_encode(&pair).unwrap())));
+        let log = EthLog {
+            address: TOKEN,
+            topics: vec![event.topic(), pair_hash, [0x22; 32], [0x33; 32]],
+            data: word(&[0x03]).to_vec(),
+        };
+        assert_eq!(
+            event.decode(&log),
+            Ok(vec![
+                AbiValue::FixedBytes(pair_hash.to_vec()),
+                AbiValue::FixedBytes(vec![0x22; 32]),
+                AbiValue::FixedBytes(vec![0x33; 32]),
+                AbiValue::Uint(3.into(), 8),
+            ]),
+        );
+    }
+}
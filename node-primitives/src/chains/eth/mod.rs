@@ -0,0 +1,166 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod abi;
+mod address;
+mod eip1271;
+mod event;
+mod rlp;
+mod transaction;
+
+pub use abi::*;
+pub use address::*;
+pub use eip1271::*;
+pub use event::*;
+pub use rlp::*;
+pub use transaction::*;
+
+use sp_core::bounded::alloc::{string::{String, ToString}, format, vec, vec::Vec};
+use sp_core::U256;
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    inner_ecdsa_recover_pubkey_with_policy, inner_ecdsa_verify_with_policy, sha2_256, Hash256, LowSPolicy, PubkeyFormat, VerifyError,
+};
+
+/// Ethereum rejects high-S transaction signatures (EIP-2), as do contract `ecrecover` wrappers.
+pub const ETH_LOW_S_POLICY: LowSPolicy = LowSPolicy::Strict;
+
+/// Verify eth ecdsa signature(sha2_256)
+pub fn eth_ecdsa_verify(pubkey: &[u8], msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Result<(), VerifyError> {
+    inner_ecdsa_verify_with_policy(pubkey, msg, sig, hash256, to_eth_signed_message_hash, ETH_LOW_S_POLICY)
+}
+
+fn to_eth_signed_message_hash(msg: Vec<u8>) -> Vec<u8> {
+    chain_bridge::utils::to_eth_signed_message_hash(&msg, sp_io::hashing::keccak_256)
+}
+
+/// [`ChainVerifier`] of EVM chains, ECDSA over the signed message digest of [`eth_ecdsa_verify`].
+pub struct EthVerifier;
+
+impl ChainVerifier for EthVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => Ok(vec![to_eth_signed_message_hash(sha2_256(msg).to_vec())]),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => eth_ecdsa_verify(pubkey, msg, sig, None),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn recover(&self, scheme: SignatureScheme, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => {
+                inner_ecdsa_recover_pubkey_with_policy(
+                    msg,
+                    sig,
+                    None,
+                    to_eth_signed_message_hash,
+                    PubkeyFormat::Compressed,
+                    ETH_LOW_S_POLICY,
+                )
+            }
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+/// ABI encode `(uint256 chainId, address vrnPort, address consumer, uint256 nonce, uint256 number)`.
+///
+/// Addresses are 20 bytes (a zero padded 32-byte word is also accepted), `nonce` and
+/// `number` are 32-byte big-endian words.
+pub fn eth_abi_encode_for_random_num(
+    chain_id: u32,
+    vrn_port: &[u8],
+    consumer_addr: &[u8],
+    nonce: &[u8],
+    number: &[u8],
+) -> Result<Vec<u8>, String> {
+    abi_encode(&[
+        AbiValue::Uint(U256::from(chain_id), 256),
+        AbiValue::Address(abi_address(vrn_port, "vrn port")?),
+        AbiValue::Address(abi_address(consumer_addr, "consumer")?),
+        AbiValue::Uint(abi_uint256(nonce, "nonce")?, 256),
+        AbiValue::Uint(abi_uint256(number, "number")?, 256),
+    ])
+}
+
+/// Decode the output of [`eth_abi_encode_for_random_num`] into
+/// `(chain_id, vrn_port, consumer_addr, nonce, number)`, each but `chain_id` as its 32-byte
+/// word, addresses zero padded.
+pub fn decode_random_num_params_from_eth_bytes(
+    bytes: Vec<u8>,
+) -> Result<(u32, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>), String> {
+    if bytes.len() != 5 * 32 {
+        return Err(format!("invalid eth encode bytes len: {:?}", bytes.len()));
+    }
+    let types = [AbiType::Uint(256), AbiType::Address, AbiType::Address, AbiType::Uint(256), AbiType::Uint(256)];
+    match abi_decode(&types, &bytes)?.as_slice() {
+        [
+            AbiValue::Uint(chain_id, _),
+            AbiValue::Address(_),
+            AbiValue::Address(_),
+            AbiValue::Uint(_, _),
+            AbiValue::Uint(_, _),
+        ] => {
+            if *chain_id > U256::from(u32::MAX) {
+                return Err(format!("chain id {chain_id} overflows u32"));
+            }
+            Ok((
+                chain_id.as_u32(),
+                bytes[32..64].to_vec(),
+                bytes[64..96].to_vec(),
+                bytes[96..128].to_vec(),
+                bytes[128..].to_vec(),
+            ))
+        }
+        _ => Err("invalid random num params".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_num_params_round_trip() {
+        let mut vrn_port = [0u8; 32];
+        vrn_port[12..].copy_from_slice(&[0x11; 20]);
+        let nonce = [0x22; 32];
+        let number = [0x33; 32];
+        let encoded = eth_abi_encode_for_random_num(7, &[0x11; 20], &[0x44; 20], &nonce, &number).unwrap();
+        assert_eq!(encoded, eth_abi_encode_for_random_num(7, &vrn_port, &[0x44; 20], &nonce, &number).unwrap());
+
+        let (chain_id, port, consumer, decoded_nonce, decoded_number) =
+            decode_random_num_params_from_eth_bytes(encoded.clone()).unwrap();
+        assert_eq!(chain_id, 7);
+        assert_eq!(port, vrn_port.to_vec());
+        assert_eq!(consumer[..12], [0u8; 12]);
+        assert_eq!(consumer[12..], [0x44; 20]);
+        assert_eq!(decoded_nonce, nonce.to_vec());
+        assert_eq!(decoded_number, number.to_vec());
+
+        let mut dirty = encoded.clone();
+        dirty[32] = 1;
+        assert!(decode_random_num_params_from_eth_bytes(dirty).is_err());
+        assert!(decode_random_num_params_from_eth_bytes(encoded[..128].to_vec()).is_err());
+    }
+}
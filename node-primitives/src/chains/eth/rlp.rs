@@ -0,0 +1,100 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use sp_core::U256;
+
+/// RLP encode a byte string.
+pub fn rlp_encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return vec![bytes[0]];
+    }
+    let mut out = rlp_encode_length(bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// RLP encode an unsigned integer as its minimal big-endian byte string.
+pub fn rlp_encode_u256(value: U256) -> Vec<u8> {
+    let mut buf = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    let start = buf.iter().position(|b| *b != 0).unwrap_or(32);
+    rlp_encode_bytes(&buf[start..])
+}
+
+pub fn rlp_encode_u64(value: u64) -> Vec<u8> {
+    rlp_encode_u256(U256::from(value))
+}
+
+/// RLP encode a list whose items are already RLP encoded.
+pub fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len = items.iter().map(|item| item.len()).sum();
+    let mut out = rlp_encode_length(payload_len, 0xc0);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+fn rlp_encode_length(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes = (len as u64).to_be_bytes();
+    let start = len_bytes.iter().position(|b| *b != 0).unwrap_or(7);
+    let mut out = vec![offset + 55 + (8 - start) as u8];
+    out.extend_from_slice(&len_bytes[start..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // examples of the RLP section of the Ethereum yellow paper and wiki
+    #[test]
+    fn encodes_strings_and_integers() {
+        assert_eq!(rlp_encode_bytes(b"dog"), hex::decode("83646f67").unwrap());
+        assert_eq!(rlp_encode_bytes(b""), vec![0x80]);
+        assert_eq!(rlp_encode_bytes(&[0x0f]), vec![0x0f]);
+        assert_eq!(rlp_encode_bytes(&[0x80]), vec![0x81, 0x80]);
+        assert_eq!(rlp_encode_u64(0), vec![0x80]);
+        assert_eq!(rlp_encode_u64(15), vec![0x0f]);
+        assert_eq!(rlp_encode_u64(1024), vec![0x82, 0x04, 0x00]);
+        assert_eq!(rlp_encode_u256(U256::MAX), [vec![0xa0], vec![0xff; 32]].concat());
+
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        assert_eq!(rlp_encode_bytes(lorem), [&[0xb8, 0x38][..], lorem].concat());
+        let long = vec![0xaa; 1024];
+        assert_eq!(rlp_encode_bytes(&long)[..3], [0xb9, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn encodes_lists() {
+        let cat_dog = rlp_encode_list(&[rlp_encode_bytes(b"cat"), rlp_encode_bytes(b"dog")]);
+        assert_eq!(cat_dog, hex::decode("c88363617483646f67").unwrap());
+        assert_eq!(rlp_encode_list(&[]), vec![0xc0]);
+        // set theoretical representation of three, [ [], [[]], [ [], [[]] ] ]
+        let empty = rlp_encode_list(&[]);
+        let one = rlp_encode_list(core::slice::from_ref(&empty));
+        let two = rlp_encode_list(&[empty.clone(), one.clone()]);
+        assert_eq!(rlp_encode_list(&[empty, one, two]), hex::decode("c7c0c1c0c3c0c1c0").unwrap());
+
+        let items = vec![rlp_encode_bytes(b"dog"); 20];
+        assert_eq!(rlp_encode_list(&items)[..2], [0xf8, 80]);
+    }
+}
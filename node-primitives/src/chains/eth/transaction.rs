@@ -0,0 +1,405 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use sp_core::U256;
+use super::rlp::{rlp_encode_bytes, rlp_encode_list, rlp_encode_u256, rlp_encode_u64};
+use crate::{
+    crypto::{parse_ecdsa_pubkey, parse_ecdsa_signature},
+    ecdsa_recover, ecdsa_recovery_id, Hash256, LowSPolicy, VerifyError,
+};
+
+pub const EIP2930_TX_TYPE: u8 = 0x01;
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthAccessListItem {
+    pub address: [u8; 20],
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+/// Pre-typed transaction, replay protected by EIP-155 when `chain_id` is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthLegacyTransaction {
+    pub chain_id: Option<u64>,
+    pub nonce: U256,
+    pub gas_price: U256,
+    pub gas_limit: U256,
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub data: Vec<u8>,
+}
+
+/// EIP-2930 access list transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthEip2930Transaction {
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub gas_price: U256,
+    pub gas_limit: U256,
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub access_list: Vec<EthAccessListItem>,
+}
+
+/// EIP-1559 dynamic fee transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EthEip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub access_list: Vec<EthAccessListItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EthTypedTransaction {
+    Legacy(EthLegacyTransaction),
+    Eip2930(EthEip2930Transaction),
+    Eip1559(EthEip1559Transaction),
+}
+
+impl EthTypedTransaction {
+    /// Encoded transaction whose keccak256 hash is signed by the committee.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        match self {
+            EthTypedTransaction::Legacy(tx) => {
+                let mut fields = tx.fields();
+                if let Some(chain_id) = tx.chain_id {
+                    fields.push(rlp_encode_u64(chain_id));
+                    fields.push(rlp_encode_bytes(&[]));
+                    fields.push(rlp_encode_bytes(&[]));
+                }
+                rlp_encode_list(&fields)
+            }
+            EthTypedTransaction::Eip2930(tx) => typed_envelope(EIP2930_TX_TYPE, &tx.fields()),
+            EthTypedTransaction::Eip1559(tx) => typed_envelope(EIP1559_TX_TYPE, &tx.fields()),
+        }
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        sp_io::hashing::keccak_256(&self.signing_payload())
+    }
+
+    /// Chain the transaction is replay protected for, `None` for a pre-EIP-155 legacy one.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            EthTypedTransaction::Legacy(tx) => tx.chain_id,
+            EthTypedTransaction::Eip2930(tx) => Some(tx.chain_id),
+            EthTypedTransaction::Eip1559(tx) => Some(tx.chain_id),
+        }
+    }
+
+    fn encode_signed(&self, recovery_id: u8, r: &[u8], s: &[u8]) -> Vec<u8> {
+        let r = rlp_encode_u256(U256::from_big_endian(r));
+        let s = rlp_encode_u256(U256::from_big_endian(s));
+        match self {
+            EthTypedTransaction::Legacy(tx) => {
+                let v = match tx.chain_id {
+                    Some(chain_id) => U256::from(chain_id) * 2 + 35 + recovery_id,
+                    None => U256::from(27 + recovery_id),
+                };
+                let mut fields = tx.fields();
+                fields.extend([rlp_encode_u256(v), r, s]);
+                rlp_encode_list(&fields)
+            }
+            EthTypedTransaction::Eip2930(tx) => {
+                let mut fields = tx.fields();
+                fields.extend([rlp_encode_u64(recovery_id as u64), r, s]);
+                typed_envelope(EIP2930_TX_TYPE, &fields)
+            }
+            EthTypedTransaction::Eip1559(tx) => {
+                let mut fields = tx.fields();
+                fields.extend([rlp_encode_u64(recovery_id as u64), r, s]);
+                typed_envelope(EIP1559_TX_TYPE, &fields)
+            }
+        }
+    }
+}
+
+impl EthLegacyTransaction {
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp_encode_u256(self.nonce),
+            rlp_encode_u256(self.gas_price),
+            rlp_encode_u256(self.gas_limit),
+            encode_to(&self.to),
+            rlp_encode_u256(self.value),
+            rlp_encode_bytes(&self.data),
+        ]
+    }
+}
+
+impl EthEip2930Transaction {
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp_encode_u64(self.chain_id),
+            rlp_encode_u256(self.nonce),
+            rlp_encode_u256(self.gas_price),
+            rlp_encode_u256(self.gas_limit),
+            encode_to(&self.to),
+            rlp_encode_u256(self.value),
+            rlp_encode_bytes(&self.data),
+            encode_access_list(&self.access_list),
+        ]
+    }
+}
+
+impl EthEip1559Transaction {
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp_encode_u64(self.chain_id),
+            rlp_encode_u256(self.nonce),
+            rlp_encode_u256(self.max_priority_fee_per_gas),
+            rlp_encode_u256(self.max_fee_per_gas),
+            rlp_encode_u256(self.gas_limit),
+            encode_to(&self.to),
+            rlp_encode_u256(self.value),
+            rlp_encode_bytes(&self.data),
+            encode_access_list(&self.access_list),
+        ]
+    }
+}
+
+fn typed_envelope(tx_type: u8, fields: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = vec![tx_type];
+    out.extend(rlp_encode_list(fields));
+    out
+}
+
+fn encode_to(to: &Option<[u8; 20]>) -> Vec<u8> {
+    match to {
+        Some(to) => rlp_encode_bytes(to),
+        None => rlp_encode_bytes(&[]),
+    }
+}
+
+fn encode_access_list(access_list: &[EthAccessListItem]) -> Vec<u8> {
+    let items: Vec<Vec<u8>> = access_list
+        .iter()
+        .map(|item| {
+            let keys: Vec<Vec<u8>> = item.storage_keys.iter().map(|key| rlp_encode_bytes(key)).collect();
+            rlp_encode_list(&[rlp_encode_bytes(&item.address), rlp_encode_list(&keys)])
+        })
+        .collect();
+    rlp_encode_list(&items)
+}
+
+/// Assemble the signed raw transaction from a committee signature over `tx.signing_hash()`.
+///
+/// `sig` is `r || s` or `r || s || v`. When `v` is missing the recovery id is derived by
+/// recovering candidates against `pubkey`, otherwise the given `v` must recover to `pubkey`
+/// and, in EIP-155 form, encode the chain id of `tx`, `InvalidRecoveryId` otherwise.
+/// Returns the signed raw transaction and its transaction hash.
+pub fn eth_assemble_signed_transaction(
+    tx: &EthTypedTransaction,
+    pubkey: &[u8],
+    sig: &[u8],
+) -> Result<(Vec<u8>, [u8; 32]), VerifyError> {
+    let expected = parse_ecdsa_pubkey(pubkey)?.serialize_compressed();
+    let candidates = match sig.len() {
+        64 => vec![0, 1],
+        65 => {
+            let v = sig[64];
+            if v >= 35 && tx.chain_id() != Some((v as u64 - 35) / 2) {
+                return Err(VerifyError::InvalidRecoveryId);
+            }
+            vec![ecdsa_recovery_id(v)?]
+        }
+        len => return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: len as u32 }),
+    };
+    let mut signature = parse_ecdsa_signature(&sig[..64], LowSPolicy::Allow)?;
+    // Ethereum rejects high-S signatures (EIP-2), negating s flips the recovery id.
+    let high_s = signature.s.is_high();
+    if high_s {
+        signature.normalize_s();
+    }
+    let compact = signature.serialize();
+
+    let payload = tx.signing_payload();
+    let recovery_id = candidates
+        .into_iter()
+        .map(|id| if high_s { id ^ 1 } else { id })
+        .find(|id| {
+            let mut sig = compact.to_vec();
+            sig.push(*id);
+            ecdsa_recover(&payload, &sig, Some(Hash256::Keccak256)) == expected
+        })
+        .ok_or(VerifyError::BadSignature)?;
+
+    let raw = tx.encode_signed(recovery_id, &compact[..32], &compact[32..]);
+    let hash = sp_io::hashing::keccak_256(&raw);
+    Ok((raw, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // secret key [0x46; 32], the EIP-155 example signer
+    const PUBKEY: &str = "024bc2a31265153f07e70e0bab08724e6b85e217f8cd628ceb62974247bb493382";
+    // EIP-155 example signature, recovery id 0
+    const EIP155_SIG: &str = "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa63627667cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn legacy(chain_id: Option<u64>) -> EthTypedTransaction {
+        EthTypedTransaction::Legacy(EthLegacyTransaction {
+            chain_id,
+            nonce: 9.into(),
+            gas_price: U256::from(20_000_000_000u64),
+            gas_limit: 21000.into(),
+            to: Some([0x35; 20]),
+            value: U256::from(1_000_000_000_000_000_000u64),
+            data: vec![],
+        })
+    }
+
+    fn eip2930() -> EthTypedTransaction {
+        EthTypedTransaction::Eip2930(EthEip2930Transaction {
+            chain_id: 1,
+            nonce: 1.into(),
+            gas_price: U256::from(20_000_000_000u64),
+            gas_limit: 30000.into(),
+            to: Some([0x35; 20]),
+            value: 0.into(),
+            data: vec![0xde, 0xad, 0xbe, 0xef],
+            access_list: vec![EthAccessListItem { address: [0x11; 20], storage_keys: vec![[0x22; 32]] }],
+        })
+    }
+
+    fn eip1559() -> EthTypedTransaction {
+        EthTypedTransaction::Eip1559(EthEip1559Transaction {
+            chain_id: 5,
+            nonce: 2.into(),
+            max_priority_fee_per_gas: U256::from(1_000_000_000u64),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            gas_limit: 50000.into(),
+            to: None,
+            value: 0.into(),
+            data: vec![0x60, 0x80, 0x60, 0x40, 0x52],
+            access_list: vec![],
+        })
+    }
+
+    fn assemble(tx: &EthTypedTransaction, sig: &str) -> Result<(Vec<u8>, [u8; 32]), VerifyError> {
+        eth_assemble_signed_transaction(tx, &hex::decode(PUBKEY).unwrap(), &hex::decode(sig).unwrap())
+    }
+
+    #[test]
+    fn eip155_example() {
+        let tx = legacy(Some(1));
+        assert_eq!(
+            hex::encode(tx.signing_payload()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080",
+        );
+        assert_eq!(hex::encode(tx.signing_hash()), "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53");
+
+        let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+        let (signed, hash) = assemble(&tx, EIP155_SIG).unwrap();
+        assert_eq!(hex::encode(&signed), raw);
+        assert_eq!(hash, sp_io::hashing::keccak_256(&signed));
+        for v in ["25", "1b", "00"] {
+            assert_eq!(hex::encode(assemble(&tx, &format!("{EIP155_SIG}{v}")).unwrap().0), raw);
+        }
+        assert_eq!(assemble(&tx, &format!("{EIP155_SIG}26")), Err(VerifyError::BadSignature));
+    }
+
+    #[test]
+    fn signed_raw_transactions() {
+        // (transaction, r || s, v, signed raw transaction, transaction hash)
+        let vectors = [
+            (
+                legacy(None),
+                "7592aab5d43618dda13fba71e3993cd7517a712d3da49664c06ee1bd3d1f70af52679cffadd3b7dacadddeb9c4aacad3d6f7b12dc4a630b8659a6329029d17ff",
+                "1c",
+                "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000801ca07592aab5d43618dda13fba71e3993cd7517a712d3da49664c06ee1bd3d1f70afa052679cffadd3b7dacadddeb9c4aacad3d6f7b12dc4a630b8659a6329029d17ff",
+                "4e48b625833e45cfa11ee6b49fb624001fac14ba5b2ed143f62f2f3b2ff4590c",
+            ),
+            (
+                legacy(Some(1)),
+                "e5740e63bad28081ed7cf654dd6c19029ca03382fc05ab5f5dda81f2c55b845b58f8172555a47731c9c8c94b882847b4bcf81d619c6b6bda0280c27911bdf22a",
+                "26",
+                "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008026a0e5740e63bad28081ed7cf654dd6c19029ca03382fc05ab5f5dda81f2c55b845ba058f8172555a47731c9c8c94b882847b4bcf81d619c6b6bda0280c27911bdf22a",
+                "f39f820bb454631d7d4e5dc1000a78438177e1a2e73c42cd02efa0825ffc83f3",
+            ),
+            (
+                eip2930(),
+                "ec6d499aefd540e90357f1004a136049d1f7df5ad99c44c46e3ed4169e40acb65ee4f618eaa14c6d6614418d258bbbcef40dfd42b01beac9ac072d27fcd79d06",
+                "00",
+                "01f8a301018504a817c8008275309435353535353535353535353535353535353535358084deadbeeff838f7941111111111111111111111111111111111111111e1a0222222222222222222222222222222222222222222222222222222222222222280a0ec6d499aefd540e90357f1004a136049d1f7df5ad99c44c46e3ed4169e40acb6a05ee4f618eaa14c6d6614418d258bbbcef40dfd42b01beac9ac072d27fcd79d06",
+                "591ff2535cfb31950f79986ccadc57847090ac046ddfcee9f7cb1e5564df2ef1",
+            ),
+            (
+                eip1559(),
+                "71550e6c83a9381f35c568d1a80e11fa3e0efc97dfd0e0f17492a2edb64c37a9367370d2b1635073d6597195ab7a061e125db5c0ef6ee67cf051cd7578f04b9c",
+                "1b",
+                "02f85c0502843b9aca008506fc23ac0082c3508080856080604052c080a071550e6c83a9381f35c568d1a80e11fa3e0efc97dfd0e0f17492a2edb64c37a9a0367370d2b1635073d6597195ab7a061e125db5c0ef6ee67cf051cd7578f04b9c",
+                "8082c803bd8ff2c0f4108eba2fc4118cfd5ea72b9b5c9b24579968dda4dd944f",
+            ),
+        ];
+        for (tx, sig, v, raw, hash) in vectors {
+            for sig in [sig.to_string(), format!("{sig}{v}")] {
+                let (signed, tx_hash) = assemble(&tx, &sig).unwrap();
+                assert_eq!(hex::encode(signed), raw);
+                assert_eq!(hex::encode(tx_hash), hash);
+            }
+        }
+    }
+
+    #[test]
+    fn normalizes_high_s() {
+        let tx = legacy(Some(1));
+        let low_s = assemble(&tx, EIP155_SIG).unwrap();
+        let mut high_s = secp256k1::Signature::parse_slice(&hex::decode(EIP155_SIG).unwrap()).unwrap();
+        high_s.s = -high_s.s;
+        let mut high_s = high_s.serialize().to_vec();
+        assert_eq!(assemble(&tx, &hex::encode(&high_s)).unwrap(), low_s);
+        // negating s flips the recovery id carried by v
+        high_s.push(38);
+        assert_eq!(assemble(&tx, &hex::encode(&high_s)).unwrap(), low_s);
+    }
+
+    #[test]
+    fn rejects_mismatched_signatures() {
+        // v = 39 encodes chain id 2, v = 37 chain id 1
+        assert_eq!(assemble(&legacy(Some(1)), &format!("{EIP155_SIG}27")), Err(VerifyError::InvalidRecoveryId));
+        assert_eq!(assemble(&legacy(None), &format!("{EIP155_SIG}25")), Err(VerifyError::InvalidRecoveryId));
+        assert_eq!(assemble(&eip1559(), &format!("{EIP155_SIG}25")), Err(VerifyError::InvalidRecoveryId));
+        assert_eq!(assemble(&legacy(Some(1)), &format!("{EIP155_SIG}1d")), Err(VerifyError::InvalidRecoveryId));
+        // signed over another transaction
+        assert_eq!(assemble(&legacy(None), EIP155_SIG), Err(VerifyError::BadSignature));
+        assert_eq!(
+            assemble(&legacy(Some(1)), &EIP155_SIG[..126]),
+            Err(VerifyError::InvalidSignatureLength { expected: 65, actual: 63 }),
+        );
+        let sig = hex::decode(EIP155_SIG).unwrap();
+        let mut off_curve = [0xffu8; 33];
+        off_curve[0] = 2;
+        assert_eq!(
+            eth_assemble_signed_transaction(&legacy(Some(1)), &off_curve, &sig),
+            Err(VerifyError::InvalidPublicKey),
+        );
+        assert_eq!(
+            eth_assemble_signed_transaction(&legacy(Some(1)), &[2; 33], &sig),
+            Err(VerifyError::BadSignature),
+        );
+    }
+}
@@ -15,35 +15,67 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::{string::{ToString, String}, format};
 use chain_bridge::utils::disintegrate_fil_msg;
 use secp256k1::Message;
-use crate::bls_verify;
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use crate::{
+    bls_verify,
+    chains::{ChainVerifier, SignatureScheme},
+    crypto::{parse_ecdsa_pubkey, parse_ecdsa_signature},
+    LowSPolicy, VerifyError,
+};
 
-pub fn verify_filecoin(pubkey: &[u8], raw: &[u8], sig: &[u8], engine: &str) -> Result<(), String> {
-    let msg_vec = match disintegrate_fil_msg(&hex::encode(raw), engine) {
-        Ok(param) => param.1,
-        Err(e) => {
-            return Err(e);
-        }
-    };
+/// Filecoin recovers secp256k1 signers without a low-S check.
+pub const FIL_LOW_S_POLICY: LowSPolicy = LowSPolicy::Allow;
+
+pub fn verify_filecoin(pubkey: &[u8], raw: &[u8], sig: &[u8], engine: &str) -> Result<(), VerifyError> {
+    // unknown engines panic in `disintegrate_fil_msg`
+    if !matches!(engine, "ECDSA" | "BLS") {
+        return Err(VerifyError::UnsupportedEngine);
+    }
+    let (_, msg_vec) = disintegrate_fil_msg(&hex::encode(raw), engine).map_err(|_| VerifyError::InvalidMessage)?;
     match engine {
         "ECDSA" => {
+            // 65-byte signature with recovery id or bare `r || s`
+            if sig.len() != 65 && sig.len() != 64 {
+                return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: sig.len() as u32 });
+            }
             let mut msg = [0; 32];
             msg.copy_from_slice(&msg_vec);
             let message = Message::parse(&msg);
-            let signature = secp256k1::Signature::parse_slice(&sig[..64]).map_err(|e| e.to_string())?;
-            let pubkey = secp256k1::PublicKey::parse_slice(pubkey, None).map_err(|e| e.to_string())?;
+            let signature = parse_ecdsa_signature(&sig[..64], FIL_LOW_S_POLICY)?;
+            let pubkey = parse_ecdsa_pubkey(pubkey)?;
             if !secp256k1::verify(&message, &signature, &pubkey) {
-                return Err("filecoin ecdsa signature verify failed".to_string());
+                return Err(VerifyError::BadSignature);
             }
         }
-        "BLS" => {
-            let mut msg = [0; 38];
-            msg.copy_from_slice(&msg_vec);
-            bls_verify(pubkey, &msg, sig).map_err(|e| format!("filecoin bls signature verify failed for: {e:?}"))?;
-        }
-        _ => return Err(format!("unsupport engine: {engine:?} to verify filecoin signature"))
+        "BLS" => bls_verify(pubkey, &msg_vec, sig)?,
+        _ => return Err(VerifyError::UnsupportedEngine),
     }
     Ok(())
 }
+
+/// [`ChainVerifier`] of Filecoin, secp256k1 ECDSA or BLS over the message CID.
+pub struct FilecoinVerifier;
+
+impl FilecoinVerifier {
+    fn engine(scheme: SignatureScheme) -> Result<&'static str, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => Ok("ECDSA"),
+            SignatureScheme::Bls => Ok("BLS"),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+impl ChainVerifier for FilecoinVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        let engine = Self::engine(scheme)?;
+        let (_, msg_vec) = disintegrate_fil_msg(&hex::encode(msg), engine).map_err(|_| VerifyError::InvalidMessage)?;
+        Ok(vec![msg_vec])
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        verify_filecoin(pubkey, msg, sig, Self::engine(scheme)?)
+    }
+}
@@ -20,9 +20,19 @@ pub mod eth;
 pub mod ckb;
 pub mod tron;
 pub mod filecoin;
+pub mod solana;
+pub mod aptos;
+pub mod sui;
+pub mod starknet;
+pub mod verify;
 
 pub use btc::*;
 pub use eth::*;
 pub use ckb::*;
 pub use tron::*;
 pub use filecoin::*;
+pub use solana::*;
+pub use aptos::*;
+pub use sui::*;
+pub use starknet::*;
+pub use verify::*;
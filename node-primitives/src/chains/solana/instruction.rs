@@ -0,0 +1,411 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{format, string::String, vec::Vec};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use super::{SolanaAccountRef, SolanaCompiledInstruction, SolanaMessage};
+
+/// `11111111111111111111111111111111`
+pub const SOLANA_SYSTEM_PROGRAM_ID: [u8; 32] = [0; 32];
+/// `TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA`
+pub const SOLANA_TOKEN_PROGRAM_ID: [u8; 32] = [
+    6, 221, 246, 225, 215, 101, 161, 147, 217, 203, 225, 70, 206, 235, 121, 172,
+    28, 180, 133, 237, 95, 91, 55, 145, 58, 140, 245, 133, 126, 255, 0, 169,
+];
+/// `TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb`
+pub const SOLANA_TOKEN_2022_PROGRAM_ID: [u8; 32] = [
+    6, 221, 246, 225, 238, 117, 143, 222, 24, 66, 93, 188, 228, 108, 205, 218,
+    182, 26, 252, 77, 131, 185, 13, 39, 254, 189, 249, 40, 216, 161, 139, 252,
+];
+/// `ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL`
+pub const SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID: [u8; 32] = [
+    140, 151, 37, 143, 78, 36, 137, 241, 187, 61, 16, 41, 20, 142, 13, 131,
+    11, 90, 19, 153, 218, 255, 16, 132, 4, 142, 123, 216, 219, 233, 248, 89,
+];
+
+const SYSTEM_TRANSFER: u32 = 2;
+const TOKEN_TRANSFER: u8 = 3;
+const TOKEN_TRANSFER_CHECKED: u8 = 12;
+const ATA_CREATE: u8 = 0;
+const ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+/// Instruction of a Solana message, decoded for the programs bridge withdrawals use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolanaInstruction {
+    /// System program `Transfer` of lamports.
+    SystemTransfer { from: SolanaAccountRef, to: SolanaAccountRef, lamports: u64 },
+    /// Token or Token-2022 `Transfer`, or `TransferChecked` with its mint and decimals.
+    TokenTransfer {
+        program_id: [u8; 32],
+        source: SolanaAccountRef,
+        destination: SolanaAccountRef,
+        authority: SolanaAccountRef,
+        amount: u64,
+        mint: Option<SolanaAccountRef>,
+        decimals: Option<u8>,
+    },
+    /// Associated token account `Create`, or `CreateIdempotent` when `idempotent`.
+    CreateAssociatedTokenAccount {
+        payer: SolanaAccountRef,
+        account: SolanaAccountRef,
+        wallet: SolanaAccountRef,
+        mint: SolanaAccountRef,
+        token_program: SolanaAccountRef,
+        idempotent: bool,
+    },
+    /// Any other instruction, including the remaining ones of the programs above.
+    Other { program_id: [u8; 32], data: Vec<u8> },
+}
+
+impl SolanaInstruction {
+    /// Decode `instruction` of `message`, failing on malformed data or missing accounts
+    /// of a recognized instruction.
+    pub fn decode(message: &SolanaMessage, instruction: &SolanaCompiledInstruction) -> Result<Self, String> {
+        let program_id = *message
+            .static_account_key(instruction.program_id_index as usize)
+            .ok_or_else(|| format!("invalid program index {}", instruction.program_id_index))?;
+        let account = |i: usize| -> Result<SolanaAccountRef, String> {
+            let index = *instruction.accounts.get(i).ok_or_else(|| format!("missing account {i} of instruction"))?;
+            message.account_ref(index as usize).ok_or_else(|| format!("invalid account index {index}"))
+        };
+        let data = instruction.data.as_slice();
+        let other = || Ok(Self::Other { program_id, data: data.to_vec() });
+
+        match program_id {
+            SOLANA_SYSTEM_PROGRAM_ID => {
+                if data.get(..4) != Some(&SYSTEM_TRANSFER.to_le_bytes()[..]) {
+                    return other();
+                }
+                Ok(Self::SystemTransfer { from: account(0)?, to: account(1)?, lamports: read_u64(data, 4, 12)? })
+            }
+            SOLANA_TOKEN_PROGRAM_ID | SOLANA_TOKEN_2022_PROGRAM_ID => match data.first() {
+                Some(&TOKEN_TRANSFER) => Ok(Self::TokenTransfer {
+                    program_id,
+                    source: account(0)?,
+                    destination: account(1)?,
+                    authority: account(2)?,
+                    amount: read_u64(data, 1, 9)?,
+                    mint: None,
+                    decimals: None,
+                }),
+                Some(&TOKEN_TRANSFER_CHECKED) => {
+                    let amount = read_u64(data, 1, 10)?;
+                    Ok(Self::TokenTransfer {
+                        program_id,
+                        source: account(0)?,
+                        mint: Some(account(1)?),
+                        destination: account(2)?,
+                        authority: account(3)?,
+                        amount,
+                        decimals: Some(data[9]),
+                    })
+                }
+                _ => other(),
+            },
+            SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID => {
+                let idempotent = match data {
+                    [] | [ATA_CREATE] => false,
+                    [ATA_CREATE_IDEMPOTENT] => true,
+                    _ => return other(),
+                };
+                Ok(Self::CreateAssociatedTokenAccount {
+                    payer: account(0)?,
+                    account: account(1)?,
+                    wallet: account(2)?,
+                    mint: account(3)?,
+                    token_program: account(5)?,
+                    idempotent,
+                })
+            }
+            _ => other(),
+        }
+    }
+}
+
+/// Little-endian u64 at `data[offset..]`, `data` being exactly `len` bytes.
+fn read_u64(data: &[u8], offset: usize, len: usize) -> Result<u64, String> {
+    if data.len() != len {
+        return Err(format!("invalid instruction data length: {:?}", data.len()));
+    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Asset moved by a [`SolanaValueMovement`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolanaAsset {
+    /// Native SOL, in lamports.
+    Sol,
+    /// Token of `program_id`, the mint is only known for checked transfers.
+    Token { program_id: [u8; 32], mint: Option<SolanaAccountRef>, decimals: Option<u8> },
+}
+
+/// Transfer of `amount` of `asset` from `from` to `to`, signed by `authority`.
+///
+/// Token transfers move between token accounts, not their owners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolanaValueMovement {
+    pub asset: SolanaAsset,
+    pub from: SolanaAccountRef,
+    pub to: SolanaAccountRef,
+    pub authority: SolanaAccountRef,
+    pub amount: u64,
+}
+
+impl SolanaMessage {
+    /// Decode every instruction, in execution order.
+    pub fn decode_instructions(&self) -> Result<Vec<SolanaInstruction>, String> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .map(|(i, instruction)| {
+                SolanaInstruction::decode(self, instruction).map_err(|e| format!("{e} at instruction {i}"))
+            })
+            .collect()
+    }
+
+    /// Lamport and token transfers of the message, in execution order.
+    ///
+    /// Instructions decoded as [`SolanaInstruction::Other`] may move value too, policy
+    /// code should reject the programs and instructions it doesn't expect.
+    pub fn value_movements(&self) -> Result<Vec<SolanaValueMovement>, String> {
+        Ok(self
+            .decode_instructions()?
+            .into_iter()
+            .filter_map(|instruction| match instruction {
+                SolanaInstruction::SystemTransfer { from, to, lamports } => {
+                    Some(SolanaValueMovement { asset: SolanaAsset::Sol, from, to, authority: from, amount: lamports })
+                }
+                SolanaInstruction::TokenTransfer { program_id, source, destination, authority, amount, mint, decimals } => {
+                    Some(SolanaValueMovement {
+                        asset: SolanaAsset::Token { program_id, mint, decimals },
+                        from: source,
+                        to: destination,
+                        authority,
+                        amount,
+                    })
+                }
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+/// Program derived address of `seeds` and the bump seed found for it, the first
+/// off-curve address counting the bump down from 255 to 1, as the runtime does.
+pub fn solana_find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<([u8; 32], u8)> {
+    if seeds.len() > 15 || seeds.iter().any(|seed| seed.len() > 32) {
+        return None;
+    }
+    (1..=u8::MAX).rev().find_map(|bump| {
+        let mut preimage = seeds.concat();
+        preimage.push(bump);
+        preimage.extend_from_slice(program_id);
+        preimage.extend_from_slice(b"ProgramDerivedAddress");
+        let address = sp_io::hashing::sha2_256(&preimage);
+        CompressedEdwardsY(address).decompress().is_none().then_some((address, bump))
+    })
+}
+
+/// Associated token account of `wallet` for `mint` under `token_program`, `None` when no
+/// bump seed yields an off-curve address.
+pub fn solana_associated_token_address(
+    wallet: &[u8; 32],
+    mint: &[u8; 32],
+    token_program: &[u8; 32],
+) -> Option<[u8; 32]> {
+    solana_find_program_address(&[wallet, token_program, mint], &SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID)
+        .map(|(address, _)| address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::{solana_parse_address, SolanaAddressTableLookup, SolanaMessageHeader, SolanaMessageVersion};
+    use sp_core::bounded::alloc::vec;
+
+    const WALLET: [u8; 32] = [1; 32];
+    const RECIPIENT: [u8; 32] = [2; 32];
+    const MINT: [u8; 32] = [5; 32];
+    const COMPUTE_BUDGET: [u8; 32] = [9; 32];
+
+    fn ix(program_id_index: u8, accounts: &[u8], data: &[u8]) -> SolanaCompiledInstruction {
+        SolanaCompiledInstruction { program_id_index, accounts: accounts.to_vec(), data: data.to_vec() }
+    }
+
+    /// Withdrawal of 1_000_000 token units to a freshly created recipient token account,
+    /// plus 5000 lamports to the recipient and a compute budget instruction.
+    fn withdrawal() -> SolanaMessage {
+        let source = solana_associated_token_address(&WALLET, &MINT, &SOLANA_TOKEN_PROGRAM_ID).unwrap();
+        let destination = solana_associated_token_address(&RECIPIENT, &MINT, &SOLANA_TOKEN_PROGRAM_ID).unwrap();
+        let mut transfer_checked = vec![TOKEN_TRANSFER_CHECKED];
+        transfer_checked.extend(1_000_000u64.to_le_bytes());
+        transfer_checked.push(6);
+        let mut system_transfer = SYSTEM_TRANSFER.to_le_bytes().to_vec();
+        system_transfer.extend(5000u64.to_le_bytes());
+        SolanaMessage {
+            version: SolanaMessageVersion::Legacy,
+            header: SolanaMessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 5,
+            },
+            // 0 wallet, 1 source, 2 destination, 3 recipient, 4 mint, 5 system, 6 token,
+            // 7 associated token, 8 compute budget
+            account_keys: vec![
+                WALLET,
+                source,
+                destination,
+                RECIPIENT,
+                MINT,
+                SOLANA_SYSTEM_PROGRAM_ID,
+                SOLANA_TOKEN_PROGRAM_ID,
+                SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID,
+                COMPUTE_BUDGET,
+            ],
+            recent_blockhash: [3; 32],
+            instructions: vec![
+                ix(8, &[], &[3, 1, 0, 0, 0, 0, 0, 0, 0]),
+                ix(7, &[0, 2, 3, 4, 5, 6], &[ATA_CREATE_IDEMPOTENT]),
+                ix(6, &[1, 4, 2, 0], &transfer_checked),
+                ix(5, &[0, 3], &system_transfer),
+            ],
+            address_table_lookups: vec![],
+        }
+    }
+
+    #[test]
+    fn associated_token_address() {
+        // computed independently following the runtime's `find_program_address`
+        let wallet = solana_parse_address("AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9").unwrap();
+        assert_eq!(
+            solana_associated_token_address(&wallet, &MINT, &SOLANA_TOKEN_PROGRAM_ID).map(hex::encode),
+            Some("86edf4a177b3363014dab10a6e22dc1ac1499c2c141bd4355f21c1484cb22551".into()),
+        );
+        assert_eq!(
+            solana_find_program_address(&[&RECIPIENT, &SOLANA_TOKEN_PROGRAM_ID, &MINT], &SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID)
+                .map(|(address, bump)| (hex::encode(address), bump)),
+            Some(("80fcf47b405db9d05c8c1f6331eba32e29143a84f727bf303ea8b9a00b7f5973".into(), 254)),
+        );
+        assert_eq!(
+            solana_find_program_address(&[&[1]], &SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID)
+                .map(|(address, bump)| (hex::encode(address), bump)),
+            Some(("87cb29e16af15ad0ac79f94c131e326dbd2409c4ff16b6795cd65e3ae0f9a3c4".into(), 252)),
+        );
+        assert_eq!(solana_find_program_address(&[&[0; 33]], &SOLANA_ASSOCIATED_TOKEN_PROGRAM_ID), None);
+    }
+
+    #[test]
+    fn decode_withdrawal_instructions() {
+        let message = SolanaMessage::decode(&withdrawal().encode()).unwrap();
+        let key = SolanaAccountRef::Key;
+        let source = key(message.account_keys[1]);
+        let destination = key(message.account_keys[2]);
+        assert_eq!(
+            message.decode_instructions().unwrap(),
+            vec![
+                SolanaInstruction::Other { program_id: COMPUTE_BUDGET, data: vec![3, 1, 0, 0, 0, 0, 0, 0, 0] },
+                SolanaInstruction::CreateAssociatedTokenAccount {
+                    payer: key(WALLET),
+                    account: destination,
+                    wallet: key(RECIPIENT),
+                    mint: key(MINT),
+                    token_program: key(SOLANA_TOKEN_PROGRAM_ID),
+                    idempotent: true,
+                },
+                SolanaInstruction::TokenTransfer {
+                    program_id: SOLANA_TOKEN_PROGRAM_ID,
+                    source,
+                    destination,
+                    authority: key(WALLET),
+                    amount: 1_000_000,
+                    mint: Some(key(MINT)),
+                    decimals: Some(6),
+                },
+                SolanaInstruction::SystemTransfer { from: key(WALLET), to: key(RECIPIENT), lamports: 5000 },
+            ],
+        );
+        assert_eq!(
+            message.value_movements().unwrap(),
+            vec![
+                SolanaValueMovement {
+                    asset: SolanaAsset::Token {
+                        program_id: SOLANA_TOKEN_PROGRAM_ID,
+                        mint: Some(key(MINT)),
+                        decimals: Some(6),
+                    },
+                    from: source,
+                    to: destination,
+                    authority: key(WALLET),
+                    amount: 1_000_000,
+                },
+                SolanaValueMovement {
+                    asset: SolanaAsset::Sol,
+                    from: key(WALLET),
+                    to: key(RECIPIENT),
+                    authority: key(WALLET),
+                    amount: 5000,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn decode_unchecked_transfer_to_lookup_account() {
+        let mut message = withdrawal();
+        message.version = SolanaMessageVersion::V0;
+        message.address_table_lookups =
+            vec![SolanaAddressTableLookup { account_key: [4; 32], writable_indexes: vec![11], readonly_indexes: vec![] }];
+        let mut transfer = vec![TOKEN_TRANSFER];
+        transfer.extend(42u64.to_le_bytes());
+        message.instructions = vec![ix(6, &[1, 9, 0], &transfer)];
+        let message = SolanaMessage::decode(&message.encode()).unwrap();
+        assert_eq!(
+            message.value_movements().unwrap(),
+            vec![SolanaValueMovement {
+                asset: SolanaAsset::Token { program_id: SOLANA_TOKEN_PROGRAM_ID, mint: None, decimals: None },
+                from: SolanaAccountRef::Key(message.account_keys[1]),
+                to: SolanaAccountRef::Lookup { table: [4; 32], index: 11 },
+                authority: SolanaAccountRef::Key(WALLET),
+                amount: 42,
+            }],
+        );
+    }
+
+    #[test]
+    fn decode_rejects_malformed_transfers() {
+        let mut short_data = withdrawal();
+        short_data.instructions[2].data.pop();
+        let mut long_data = withdrawal();
+        long_data.instructions[3].data.push(0);
+        let mut missing_account = withdrawal();
+        missing_account.instructions[2].accounts.truncate(3);
+        let mut missing_ata_account = withdrawal();
+        missing_ata_account.instructions[1].accounts.truncate(5);
+        for message in [short_data, long_data, missing_account, missing_ata_account] {
+            assert!(message.decode_instructions().is_err());
+            assert!(message.value_movements().is_err());
+        }
+
+        // other token instructions are reported, never mistaken for transfers
+        let mut approve = withdrawal();
+        approve.instructions[2].data[0] = 4;
+        assert_eq!(approve.value_movements().unwrap().len(), 1);
+        assert!(matches!(approve.decode_instructions().unwrap()[2], SolanaInstruction::Other { .. }));
+    }
+}
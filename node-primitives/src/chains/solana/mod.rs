@@ -0,0 +1,477 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod instruction;
+
+pub use instruction::*;
+
+use sp_core::bounded::alloc::{format, string::{String, ToString}, vec, vec::Vec};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    ed25519_verify, VerifyError,
+};
+
+/// Prefix bit of a versioned message, legacy messages start with the header instead.
+const VERSION_PREFIX: u8 = 0x80;
+
+/// Base58 address of an ed25519 public key.
+pub fn solana_address(pubkey: &[u8; 32]) -> String {
+    bs58::encode(pubkey).into_string()
+}
+
+/// Public key of a base58 address.
+pub fn solana_parse_address(s: &str) -> Result<[u8; 32], String> {
+    let bytes = bs58::decode(s).into_vec().map_err(|e| format!("invalid base58 address: {e:?}"))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| format!("invalid solana address length: {:?}", bytes.len()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolanaMessageVersion {
+    Legacy,
+    V0,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolanaMessageHeader {
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+}
+
+/// Instruction with its program and accounts as indexes into the message account keys.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolanaCompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// Accounts a v0 message loads from an on-chain address lookup table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolanaAddressTableLookup {
+    pub account_key: [u8; 32],
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// Account a message refers to, by key or by its entry in an address lookup table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolanaAccountRef {
+    Key([u8; 32]),
+    /// Entry `index` of the lookup table at `table`, resolved on chain.
+    Lookup { table: [u8; 32], index: u8 },
+}
+
+/// Legacy or v0 transaction message, the bytes every signer signs.
+///
+/// Account indexes address the static keys followed by the writable and then the
+/// readonly accounts of all lookup tables, in table order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolanaMessage {
+    pub version: SolanaMessageVersion,
+    pub header: SolanaMessageHeader,
+    pub account_keys: Vec<[u8; 32]>,
+    pub recent_blockhash: [u8; 32],
+    pub instructions: Vec<SolanaCompiledInstruction>,
+    /// Always empty for legacy messages.
+    pub address_table_lookups: Vec<SolanaAddressTableLookup>,
+}
+
+impl SolanaMessage {
+    /// Decode a serialized message, rejecting trailing bytes and messages the runtime
+    /// would fail to sanitize.
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(bytes);
+        let version = match bytes.first() {
+            Some(prefix) if prefix & VERSION_PREFIX != 0 => {
+                reader.u8()?;
+                match prefix & !VERSION_PREFIX {
+                    0 => SolanaMessageVersion::V0,
+                    v => return Err(format!("unsupported solana message version: {v}")),
+                }
+            }
+            _ => SolanaMessageVersion::Legacy,
+        };
+        let header = SolanaMessageHeader {
+            num_required_signatures: reader.u8()?,
+            num_readonly_signed_accounts: reader.u8()?,
+            num_readonly_unsigned_accounts: reader.u8()?,
+        };
+        let account_keys = reader.vec(|r| r.array())?;
+        let recent_blockhash = reader.array()?;
+        let instructions = reader.vec(|r| {
+            Ok(SolanaCompiledInstruction { program_id_index: r.u8()?, accounts: r.bytes()?, data: r.bytes()? })
+        })?;
+        let address_table_lookups = match version {
+            SolanaMessageVersion::Legacy => Vec::new(),
+            SolanaMessageVersion::V0 => reader.vec(|r| {
+                Ok(SolanaAddressTableLookup {
+                    account_key: r.array()?,
+                    writable_indexes: r.bytes()?,
+                    readonly_indexes: r.bytes()?,
+                })
+            })?,
+        };
+        if !reader.0.is_empty() {
+            return Err(format!("{} trailing bytes after solana message", reader.0.len()));
+        }
+        let message = Self { version, header, account_keys, recent_blockhash, instructions, address_table_lookups };
+        message.sanitize()?;
+        Ok(message)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.version == SolanaMessageVersion::V0 {
+            out.push(VERSION_PREFIX);
+        }
+        out.extend([
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ]);
+        encode_short_vec_len(&mut out, self.account_keys.len());
+        self.account_keys.iter().for_each(|key| out.extend_from_slice(key));
+        out.extend_from_slice(&self.recent_blockhash);
+        encode_short_vec_len(&mut out, self.instructions.len());
+        for instruction in &self.instructions {
+            out.push(instruction.program_id_index);
+            encode_bytes(&mut out, &instruction.accounts);
+            encode_bytes(&mut out, &instruction.data);
+        }
+        if self.version == SolanaMessageVersion::V0 {
+            encode_short_vec_len(&mut out, self.address_table_lookups.len());
+            for lookup in &self.address_table_lookups {
+                out.extend_from_slice(&lookup.account_key);
+                encode_bytes(&mut out, &lookup.writable_indexes);
+                encode_bytes(&mut out, &lookup.readonly_indexes);
+            }
+        }
+        out
+    }
+
+    fn sanitize(&self) -> Result<(), String> {
+        let header = &self.header;
+        let num_static = self.account_keys.len();
+        if header.num_readonly_signed_accounts >= header.num_required_signatures {
+            return Err("solana message without a writable fee payer".to_string());
+        }
+        if header.num_required_signatures as usize + header.num_readonly_unsigned_accounts as usize > num_static {
+            return Err("solana message header exceeds its account keys".to_string());
+        }
+        if self.address_table_lookups.iter().any(|l| l.writable_indexes.is_empty() && l.readonly_indexes.is_empty()) {
+            return Err("solana address table lookup without any index".to_string());
+        }
+        let num_accounts = self.num_account_keys();
+        if num_accounts > 256 {
+            return Err(format!("too many solana message accounts: {num_accounts}"));
+        }
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            // programs are never the fee payer and can't be loaded from a lookup table
+            let program = instruction.program_id_index as usize;
+            if program == 0 || program >= num_static {
+                return Err(format!("invalid program index {program} of instruction {i}"));
+            }
+            if let Some(account) = instruction.accounts.iter().find(|&&a| a as usize >= num_accounts) {
+                return Err(format!("invalid account index {account} of instruction {i}"));
+            }
+        }
+        for (i, key) in self.account_keys.iter().enumerate() {
+            if self.account_keys[..i].contains(key) {
+                return Err(format!("duplicate solana account key at index {i}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of static and lookup table accounts.
+    pub fn num_account_keys(&self) -> usize {
+        self.account_keys.len()
+            + self
+                .address_table_lookups
+                .iter()
+                .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+                .sum::<usize>()
+    }
+
+    /// Keys that must sign the message, the fee payer first.
+    pub fn signers(&self) -> &[[u8; 32]] {
+        &self.account_keys[..self.header.num_required_signatures as usize]
+    }
+
+    pub fn fee_payer(&self) -> &[u8; 32] {
+        &self.account_keys[0]
+    }
+
+    /// Position of `pubkey` among the required signers, which is also the position of
+    /// its signature in the transaction.
+    pub fn signer_index(&self, pubkey: &[u8; 32]) -> Option<usize> {
+        self.signers().iter().position(|signer| signer == pubkey)
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    /// Whether the account at `index` is writable, as requested by the message.
+    pub fn is_writable(&self, index: usize) -> bool {
+        let header = &self.header;
+        let num_static = self.account_keys.len();
+        let num_signed = header.num_required_signatures as usize;
+        if index < num_signed {
+            return index < num_signed - header.num_readonly_signed_accounts as usize;
+        }
+        if index < num_static {
+            return index < num_static - header.num_readonly_unsigned_accounts as usize;
+        }
+        let num_loaded_writable: usize = self.address_table_lookups.iter().map(|l| l.writable_indexes.len()).sum();
+        index < num_static + num_loaded_writable
+    }
+
+    /// Static key at `index`, `None` for accounts loaded from lookup tables.
+    pub fn static_account_key(&self, index: usize) -> Option<&[u8; 32]> {
+        self.account_keys.get(index)
+    }
+
+    /// Account at `index`, `None` past the last lookup table account.
+    pub fn account_ref(&self, index: usize) -> Option<SolanaAccountRef> {
+        if let Some(key) = self.account_keys.get(index) {
+            return Some(SolanaAccountRef::Key(*key));
+        }
+        let index = index - self.account_keys.len();
+        let writable = self.address_table_lookups.iter().flat_map(|l| l.writable_indexes.iter().map(move |i| (l, i)));
+        let readonly = self.address_table_lookups.iter().flat_map(|l| l.readonly_indexes.iter().map(move |i| (l, i)));
+        writable
+            .chain(readonly)
+            .nth(index)
+            .map(|(lookup, &index)| SolanaAccountRef::Lookup { table: lookup.account_key, index })
+    }
+}
+
+/// Verify the ed25519 signature of `pubkey` over a serialized legacy or v0 message,
+/// which must list `pubkey` among its required signers.
+pub fn verify_solana(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    let signer: &[u8; 32] = pubkey
+        .try_into()
+        .map_err(|_| VerifyError::InvalidPublicKeyLength { expected: 32, actual: pubkey.len() as u32 })?;
+    let message = SolanaMessage::decode(msg).map_err(|_| VerifyError::InvalidMessage)?;
+    if message.signer_index(signer).is_none() {
+        return Err(VerifyError::NotRequiredSigner);
+    }
+    ed25519_verify(pubkey, msg, sig)
+}
+
+/// [`ChainVerifier`] of Solana, ed25519 over the serialized message.
+pub struct SolanaVerifier;
+
+impl ChainVerifier for SolanaVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => {
+                SolanaMessage::decode(msg).map_err(|_| VerifyError::InvalidMessage)?;
+                Ok(vec![msg.to_vec()])
+            }
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => verify_solana(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+/// Compact-u16 length prefix, 7 bits per byte, least significant first.
+fn encode_short_vec_len(out: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    encode_short_vec_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("unexpected end of solana message".to_string());
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array(&mut self) -> Result<[u8; 32], String> {
+        let mut array = [0u8; 32];
+        array.copy_from_slice(self.take(32)?);
+        Ok(array)
+    }
+
+    /// Compact-u16, rejecting aliased encodings with trailing zero bytes.
+    fn short_vec_len(&mut self) -> Result<usize, String> {
+        let mut len = 0usize;
+        for i in 0..3 {
+            let byte = self.u8()?;
+            len |= ((byte & 0x7f) as usize) << (i * 7);
+            if byte & 0x80 == 0 {
+                if i > 0 && byte == 0 {
+                    return Err("non-canonical solana compact-u16".to_string());
+                }
+                if len > u16::MAX as usize {
+                    return Err("solana compact-u16 overflow".to_string());
+                }
+                return Ok(len);
+            }
+        }
+        Err("solana compact-u16 overflow".to_string())
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.short_vec_len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        let len = self.short_vec_len()?;
+        (0..len).map(|_| item(self)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+    // transfer of 1_000_000 lamports to [0x02; 32], signed with the ed25519 seed [0x01; 32]
+    const LEGACY_MESSAGE: &str = "010001038a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c02020202020202020202020202020202020202020202020202020202020202020000000000000000000000000000000000000000000000000000000000000000030303030303030303030303030303030303030303030303030303030303030301020200010c0200000040420f0000000000";
+    const LEGACY_SIG: &str = "b033059fc60d833f1027350d31401c321c45b7e54477ae7c2fa0211592a57b3592bdea62c63e1173d707a6904197cb25b7087d090d360a7caa6e4ab28da12f0d";
+    // same transfer passing one extra account from lookup table [0x04; 32]
+    const V0_MESSAGE: &str = "80010001038a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c0202020202020202020202020202020202020202020202020202020202020202000000000000000000000000000000000000000000000000000000000000000003030303030303030303030303030303030303030303030303030303030303030102030001030c0200000040420f0000000000010404040404040404040404040404040404040404040404040404040404040404010700";
+    const V0_SIG: &str = "cefa6542b9da3573861b0717bc1f960d7f183cbde32fe5665a1f72447b1d6f9aedfb0ecca7ba46015a6449deb1a24ce88d07ddbb279cdd0e1e7e9fae730d9208";
+
+    fn pubkey() -> [u8; 32] {
+        hex::decode(PUBKEY).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn address_round_trip() {
+        assert_eq!(solana_address(&pubkey()), "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9");
+        assert_eq!(solana_parse_address("AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9"), Ok(pubkey()));
+        assert_eq!(solana_address(&[0u8; 32]), "11111111111111111111111111111111");
+        assert!(solana_parse_address("1111111111111111111111111111111").is_err());
+        assert!(solana_parse_address("0OIl").is_err());
+    }
+
+    #[test]
+    fn decode_legacy_message() {
+        let bytes = hex::decode(LEGACY_MESSAGE).unwrap();
+        let message = SolanaMessage::decode(&bytes).unwrap();
+        assert_eq!(message.version, SolanaMessageVersion::Legacy);
+        assert_eq!(message.signers(), &[pubkey()]);
+        assert_eq!(message.fee_payer(), &pubkey());
+        assert_eq!(message.recent_blockhash, [3u8; 32]);
+        assert_eq!(message.instructions.len(), 1);
+        assert_eq!(message.instructions[0].program_id_index, 2);
+        assert_eq!(message.instructions[0].accounts, vec![0, 1]);
+        assert_eq!(message.instructions[0].data.len(), 12);
+        assert!(message.address_table_lookups.is_empty());
+        assert_eq!((0..3).map(|i| message.is_writable(i)).collect::<Vec<_>>(), vec![true, true, false]);
+        assert_eq!(message.encode(), bytes);
+    }
+
+    #[test]
+    fn decode_v0_message() {
+        let bytes = hex::decode(V0_MESSAGE).unwrap();
+        let message = SolanaMessage::decode(&bytes).unwrap();
+        assert_eq!(message.version, SolanaMessageVersion::V0);
+        assert_eq!(message.num_account_keys(), 4);
+        assert_eq!(
+            message.address_table_lookups,
+            vec![SolanaAddressTableLookup { account_key: [4u8; 32], writable_indexes: vec![7], readonly_indexes: vec![] }],
+        );
+        assert!(message.is_writable(3));
+        assert_eq!(message.static_account_key(3), None);
+        assert_eq!(message.account_ref(1), Some(SolanaAccountRef::Key([2u8; 32])));
+        assert_eq!(message.account_ref(3), Some(SolanaAccountRef::Lookup { table: [4u8; 32], index: 7 }));
+        assert_eq!(message.account_ref(4), None);
+        assert_eq!(message.encode(), bytes);
+    }
+
+    #[test]
+    fn decode_rejects_malformed_messages() {
+        let bytes = hex::decode(LEGACY_MESSAGE).unwrap();
+        for len in [0, 3, 50, bytes.len() - 1] {
+            assert!(SolanaMessage::decode(&bytes[..len]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(SolanaMessage::decode(&trailing).is_err());
+
+        let message = SolanaMessage::decode(&bytes).unwrap();
+        let mut unsupported = hex::decode(V0_MESSAGE).unwrap();
+        unsupported[0] = 0x81;
+        assert!(SolanaMessage::decode(&unsupported).is_err());
+
+        let mut no_payer = message.clone();
+        no_payer.header.num_readonly_signed_accounts = 1;
+        let mut program_payer = message.clone();
+        program_payer.instructions[0].program_id_index = 0;
+        let mut bad_account = message.clone();
+        bad_account.instructions[0].accounts.push(3);
+        let mut duplicate = message.clone();
+        duplicate.account_keys[1] = pubkey();
+        for message in [no_payer, program_payer, bad_account, duplicate] {
+            assert!(SolanaMessage::decode(&message.encode()).is_err());
+        }
+
+        // aliased compact-u16 for the number of account keys
+        let mut aliased = bytes[..3].to_vec();
+        aliased.extend([0x83, 0x00]);
+        aliased.extend_from_slice(&bytes[4..]);
+        assert!(SolanaMessage::decode(&aliased).is_err());
+    }
+
+    #[test]
+    fn verify_signer() {
+        for (message, sig) in [(LEGACY_MESSAGE, LEGACY_SIG), (V0_MESSAGE, V0_SIG)] {
+            let msg = hex::decode(message).unwrap();
+            let sig = hex::decode(sig).unwrap();
+            assert_eq!(verify_solana(&pubkey(), &msg, &sig), Ok(()));
+            assert_eq!(verify_solana(&[2u8; 32], &msg, &sig), Err(VerifyError::NotRequiredSigner));
+            assert_eq!(verify_solana(&pubkey(), &msg[1..], &sig), Err(VerifyError::InvalidMessage));
+            let mut tampered = SolanaMessage::decode(&msg).unwrap();
+            tampered.recent_blockhash[0] ^= 1;
+            assert_eq!(verify_solana(&pubkey(), &tampered.encode(), &sig), Err(VerifyError::BadSignature));
+        }
+    }
+}
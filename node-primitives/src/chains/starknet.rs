@@ -0,0 +1,421 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{string::{String, ToString}, vec, vec::Vec};
+use starknet_crypto::{rfc6979_generate_k, SignError, VerifyError as StarkVerifyError};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    VerifyError,
+};
+
+pub use starknet_crypto::{
+    pedersen_hash as starknet_pedersen_hash, poseidon_hash as starknet_poseidon_hash,
+    poseidon_hash_many as starknet_poseidon_hash_many, FieldElement as StarknetFelt,
+};
+
+/// `SN_MAIN`, chain id of Starknet mainnet.
+pub const STARKNET_SN_MAIN: StarknetFelt = StarknetFelt::from_mont([
+    17696389056366564951,
+    18446744073709551615,
+    18446744073709551615,
+    502562008147966918,
+]);
+
+/// `SN_SEPOLIA`, chain id of the Starknet Sepolia testnet.
+pub const STARKNET_SN_SEPOLIA: StarknetFelt = StarknetFelt::from_mont([
+    1555806712078248243,
+    18446744073708869172,
+    18446744073709551615,
+    507980251676163170,
+]);
+
+/// Order of the STARK curve generator, big endian. Private keys are nonzero and below it.
+const STARK_CURVE_ORDER: [u8; 32] = [
+    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xb7, 0x81, 0x12, 0x6d, 0xca, 0xe7, 0xb2, 0x32, 0x1e, 0x66, 0xa2, 0x41, 0xad, 0xc6, 0x4d, 0x2f,
+];
+
+/// `invoke` transaction hash prefix.
+const INVOKE_PREFIX: u64 = 0x696e766f6b65;
+const L1_GAS: u64 = 0x4c315f474153;
+const L2_GAS: u64 = 0x4c325f474153;
+const L1_DATA_GAS: u64 = 0x4c315f44415441;
+
+/// Cairo short string, ASCII of at most 31 bytes packed big endian into a felt.
+pub fn starknet_short_string(s: &str) -> Option<StarknetFelt> {
+    if s.len() > 31 || !s.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    bytes[32 - s.len()..].copy_from_slice(s.as_bytes());
+    StarknetFelt::from_bytes_be(&bytes).ok()
+}
+
+/// Felt of 32 big endian bytes, `None` for values outside the field.
+pub fn starknet_felt(bytes: &[u8]) -> Option<StarknetFelt> {
+    StarknetFelt::from_bytes_be(bytes.try_into().ok()?).ok()
+}
+
+/// `compute_hash_on_elements` of cairo-lang, Pedersen chained from zero over the
+/// elements and then their count.
+pub fn starknet_pedersen_hash_array(elements: &[StarknetFelt]) -> StarknetFelt {
+    let hash = elements.iter().fold(StarknetFelt::ZERO, |acc, e| starknet_pedersen_hash(&acc, e));
+    starknet_pedersen_hash(&hash, &StarknetFelt::from(elements.len()))
+}
+
+/// Invoke transaction of version 1, fee paid in ETH up to `max_fee`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StarknetInvokeV1 {
+    pub sender_address: StarknetFelt,
+    pub calldata: Vec<StarknetFelt>,
+    pub max_fee: StarknetFelt,
+    pub nonce: StarknetFelt,
+}
+
+impl StarknetInvokeV1 {
+    /// Transaction hash on the chain `chain_id`, the message the account signs.
+    pub fn transaction_hash(&self, chain_id: StarknetFelt) -> StarknetFelt {
+        starknet_pedersen_hash_array(&[
+            StarknetFelt::from(INVOKE_PREFIX),
+            StarknetFelt::ONE,
+            self.sender_address,
+            StarknetFelt::ZERO,
+            starknet_pedersen_hash_array(&self.calldata),
+            self.max_fee,
+            chain_id,
+            self.nonce,
+        ])
+    }
+}
+
+/// Bounds a v3 transaction pays for one resource.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StarknetResourceBounds {
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+impl StarknetResourceBounds {
+    /// `resource_name || max_amount || max_price_per_unit` packed into a single felt.
+    fn to_felt(self, resource: u64) -> StarknetFelt {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&resource.to_be_bytes());
+        bytes[8..16].copy_from_slice(&self.max_amount.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.max_price_per_unit.to_be_bytes());
+        // resource names are at most 7 bytes, so the value stays below the modulus
+        StarknetFelt::from_bytes_be(&bytes).expect("resource bound below modulus; qed")
+    }
+}
+
+/// Layer the nonce or fee of a v3 transaction is posted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StarknetDataAvailabilityMode {
+    #[default]
+    L1 = 0,
+    L2 = 1,
+}
+
+/// Invoke transaction of version 3, fee paid in STRK within the resource bounds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StarknetInvokeV3 {
+    pub sender_address: StarknetFelt,
+    pub calldata: Vec<StarknetFelt>,
+    pub nonce: StarknetFelt,
+    pub tip: u64,
+    pub l1_gas: StarknetResourceBounds,
+    pub l2_gas: StarknetResourceBounds,
+    /// Bound on L1 data gas, hashed only when present as introduced with Starknet 0.13.4.
+    pub l1_data_gas: Option<StarknetResourceBounds>,
+    pub paymaster_data: Vec<StarknetFelt>,
+    pub account_deployment_data: Vec<StarknetFelt>,
+    pub nonce_data_availability_mode: StarknetDataAvailabilityMode,
+    pub fee_data_availability_mode: StarknetDataAvailabilityMode,
+}
+
+impl StarknetInvokeV3 {
+    /// Transaction hash on the chain `chain_id`, the message the account signs.
+    pub fn transaction_hash(&self, chain_id: StarknetFelt) -> StarknetFelt {
+        let mut fee_fields = vec![
+            StarknetFelt::from(self.tip),
+            self.l1_gas.to_felt(L1_GAS),
+            self.l2_gas.to_felt(L2_GAS),
+        ];
+        if let Some(l1_data_gas) = self.l1_data_gas {
+            fee_fields.push(l1_data_gas.to_felt(L1_DATA_GAS));
+        }
+        let data_availability_modes =
+            (self.nonce_data_availability_mode as u64) << 32 | self.fee_data_availability_mode as u64;
+
+        starknet_poseidon_hash_many(&[
+            StarknetFelt::from(INVOKE_PREFIX),
+            StarknetFelt::THREE,
+            self.sender_address,
+            starknet_poseidon_hash_many(&fee_fields),
+            starknet_poseidon_hash_many(&self.paymaster_data),
+            chain_id,
+            self.nonce,
+            StarknetFelt::from(data_availability_modes),
+            starknet_poseidon_hash_many(&self.account_deployment_data),
+            starknet_poseidon_hash_many(&self.calldata),
+        ])
+    }
+}
+
+fn stark_private_key(private_key: &[u8; 32]) -> Result<StarknetFelt, String> {
+    if private_key == &[0u8; 32] || private_key >= &STARK_CURVE_ORDER {
+        return Err("invalid stark private key".to_string());
+    }
+    StarknetFelt::from_bytes_be(private_key).map_err(|_| "invalid stark private key".to_string())
+}
+
+/// Public key of a STARK curve private key, the x coordinate of its point.
+pub fn starknet_public_key(private_key: &[u8; 32]) -> Result<[u8; 32], String> {
+    Ok(starknet_crypto::get_public_key(&stark_private_key(private_key)?).to_bytes_be())
+}
+
+/// STARK curve ECDSA signature `r || s` of a transaction or message hash, with the
+/// RFC 6979 nonce the Starknet tooling derives.
+pub fn starknet_sign(private_key: &[u8; 32], hash: &[u8; 32]) -> Result<[u8; 64], String> {
+    let private_key = stark_private_key(private_key)?;
+    let hash = StarknetFelt::from_bytes_be(hash).map_err(|_| "stark message hash out of range".to_string())?;
+    let mut seed = None;
+    let signature = loop {
+        let k = rfc6979_generate_k(&hash, &private_key, seed.as_ref());
+        match starknet_crypto::sign(&private_key, &hash, &k) {
+            Ok(signature) => break signature,
+            Err(SignError::InvalidMessageHash) => return Err("stark message hash out of range".to_string()),
+            Err(SignError::InvalidK) => seed = Some(seed.unwrap_or(StarknetFelt::ZERO) + StarknetFelt::ONE),
+        }
+    };
+    let mut sig = [0u8; 64];
+    sig[..32].copy_from_slice(&signature.r.to_bytes_be());
+    sig[32..].copy_from_slice(&signature.s.to_bytes_be());
+    Ok(sig)
+}
+
+/// Verify a STARK curve ECDSA signature `r || s` of the 32-byte `hash` against the
+/// x coordinate public key.
+pub fn starknet_verify(pubkey: &[u8], hash: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    if pubkey.len() != 32 {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: 32, actual: pubkey.len() as u32 });
+    }
+    if sig.len() != 64 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 64, actual: sig.len() as u32 });
+    }
+    let pubkey = starknet_felt(pubkey).ok_or(VerifyError::InvalidPublicKey)?;
+    let hash = starknet_felt(hash).ok_or(VerifyError::InvalidMessage)?;
+    let r = starknet_felt(&sig[..32]).ok_or(VerifyError::InvalidSignature)?;
+    let s = starknet_felt(&sig[32..]).ok_or(VerifyError::InvalidSignature)?;
+    match starknet_crypto::verify(&pubkey, &hash, &r, &s) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(VerifyError::BadSignature),
+        Err(StarkVerifyError::InvalidPublicKey) => Err(VerifyError::InvalidPublicKey),
+        Err(StarkVerifyError::InvalidMessageHash) => Err(VerifyError::InvalidMessage),
+        Err(StarkVerifyError::InvalidR | StarkVerifyError::InvalidS) => Err(VerifyError::InvalidSignature),
+    }
+}
+
+/// [`ChainVerifier`] of Starknet, STARK curve ECDSA over the 32-byte big endian
+/// transaction hash, as computed by [`StarknetInvokeV1`] or [`StarknetInvokeV3`].
+pub struct StarknetVerifier;
+
+impl ChainVerifier for StarknetVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Stark => {
+                starknet_felt(msg).ok_or(VerifyError::InvalidMessage)?;
+                Ok(vec![msg.to_vec()])
+            }
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Stark => starknet_verify(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felt(hex: &str) -> StarknetFelt {
+        StarknetFelt::from_hex_be(hex).unwrap()
+    }
+
+    fn calldata() -> Vec<StarknetFelt> {
+        vec![
+            felt("0x1"),
+            felt("0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"),
+            felt("0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e"),
+            felt("0x3"),
+            felt("0x456"),
+            felt("0x3e8"),
+            felt("0x0"),
+        ]
+    }
+
+    fn invoke_v1() -> StarknetInvokeV1 {
+        StarknetInvokeV1 {
+            sender_address: felt("0x123456789abcdef"),
+            calldata: calldata(),
+            max_fee: StarknetFelt::from(10u64.pow(15)),
+            nonce: felt("0x5"),
+        }
+    }
+
+    fn invoke_v3() -> StarknetInvokeV3 {
+        StarknetInvokeV3 {
+            sender_address: felt("0x123456789abcdef"),
+            calldata: calldata(),
+            nonce: felt("0x5"),
+            tip: 7,
+            l1_gas: StarknetResourceBounds { max_amount: 0x2000, max_price_per_unit: 0x5af3107a4000 },
+            l2_gas: StarknetResourceBounds::default(),
+            l1_data_gas: None,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: StarknetDataAvailabilityMode::L1,
+            fee_data_availability_mode: StarknetDataAvailabilityMode::L1,
+        }
+    }
+
+    #[test]
+    fn hashes() {
+        // cairo-lang pedersen test vector
+        assert_eq!(
+            starknet_pedersen_hash(
+                &felt("0x03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb"),
+                &felt("0x0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a"),
+            ),
+            felt("0x30e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662"),
+        );
+        let elements = [felt("0x1"), felt("0x2"), felt("0x3")];
+        assert_eq!(
+            starknet_pedersen_hash_array(&elements),
+            felt("0xf9d95fbf356fbeda26538c92f7040abe51bf142350f73c9ee5ba7c660bae71"),
+        );
+        assert_eq!(
+            starknet_poseidon_hash(felt("0x1"), felt("0x2")),
+            felt("0x5d44a3decb2b2e0cc71071f7b802f45dd792d064f0fc7316c46514f70f9891a"),
+        );
+        assert_eq!(
+            starknet_poseidon_hash_many(&elements),
+            felt("0x2f0d8840bcf3bc629598d8a6cc80cb7c0d9e52d93dab244bbf9cd0dca0ad082"),
+        );
+        assert_eq!(
+            starknet_poseidon_hash_many(&[]),
+            felt("0x2272be0f580fd156823304800919530eaa97430e972d7213ee13f4fbf7a5dbc"),
+        );
+    }
+
+    #[test]
+    fn chain_ids_are_short_strings() {
+        assert_eq!(starknet_short_string("SN_MAIN"), Some(STARKNET_SN_MAIN));
+        assert_eq!(starknet_short_string("SN_SEPOLIA"), Some(STARKNET_SN_SEPOLIA));
+        assert_eq!(STARKNET_SN_MAIN, felt("0x534e5f4d41494e"));
+        assert_eq!(starknet_short_string(""), Some(StarknetFelt::ZERO));
+        assert_eq!(starknet_short_string(&"a".repeat(32)), None);
+        assert_eq!(starknet_short_string("é"), None);
+    }
+
+    #[test]
+    fn invoke_transaction_hashes() {
+        assert_eq!(
+            invoke_v1().transaction_hash(STARKNET_SN_SEPOLIA),
+            felt("0x6159ef39a1797903c0b7a54e41c79b7d0cad4b59391cab7b1f4f234734a2302"),
+        );
+        assert_ne!(invoke_v1().transaction_hash(STARKNET_SN_MAIN), invoke_v1().transaction_hash(STARKNET_SN_SEPOLIA));
+
+        assert_eq!(
+            invoke_v3().transaction_hash(STARKNET_SN_MAIN),
+            felt("0x5eb7d2c6ede093e775d30ae7574ec0979b9bf8ddf0416b84730281c58b0b03d"),
+        );
+        let tx = StarknetInvokeV3 {
+            l1_data_gas: Some(StarknetResourceBounds { max_amount: 0x100, max_price_per_unit: 0x10 }),
+            paymaster_data: vec![felt("0xaa")],
+            account_deployment_data: vec![felt("0xbb"), felt("0xcc")],
+            nonce_data_availability_mode: StarknetDataAvailabilityMode::L2,
+            ..invoke_v3()
+        };
+        assert_eq!(
+            tx.transaction_hash(STARKNET_SN_MAIN),
+            felt("0x1ed794eb4fae9ccbda7989bf9aba4ab59d78b32a0ffe48bb2f6b2ddfa9b74a4"),
+        );
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let mut private_key = [7u8; 32];
+        private_key[0] = 0;
+        let pubkey = starknet_public_key(&private_key).unwrap();
+        assert_eq!(hex::encode(pubkey), "077936aa14012c42335be63dad1dbb712abf8b625a72ba42f3a9565c60e2296b");
+
+        let hash = invoke_v1().transaction_hash(STARKNET_SN_SEPOLIA).to_bytes_be();
+        // signed by an independent implementation with a fixed nonce
+        let sig = hex::decode(
+            "031b88d91e173d70b5bd284f7a898d99005d9ade236ee671e0db0078996aa5990438f7f0ebb7ed02dcce144db4a12f594b26319f4630ba74a83043e4bfc1e15b",
+        )
+        .unwrap();
+        assert_eq!(starknet_verify(&pubkey, &hash, &sig), Ok(()));
+
+        let signed = starknet_sign(&private_key, &hash).unwrap();
+        assert_eq!(signed, starknet_sign(&private_key, &hash).unwrap());
+        assert_eq!(starknet_verify(&pubkey, &hash, &signed), Ok(()));
+
+        let other = invoke_v3().transaction_hash(STARKNET_SN_MAIN).to_bytes_be();
+        assert_eq!(starknet_verify(&pubkey, &other, &sig), Err(VerifyError::BadSignature));
+        assert_eq!(starknet_verify(&pubkey, &[0xff; 32], &sig), Err(VerifyError::InvalidMessage));
+        assert_eq!(starknet_verify(&pubkey, &hash, &[0u8; 64]), Err(VerifyError::InvalidSignature));
+        assert_eq!(
+            starknet_verify(&pubkey, &hash, &sig[..63]),
+            Err(VerifyError::InvalidSignatureLength { expected: 64, actual: 63 }),
+        );
+        assert_eq!(
+            starknet_verify(&pubkey[1..], &hash, &sig),
+            Err(VerifyError::InvalidPublicKeyLength { expected: 32, actual: 31 }),
+        );
+
+        assert!(starknet_public_key(&[0u8; 32]).is_err());
+        assert!(starknet_public_key(&STARK_CURVE_ORDER).is_err());
+        assert!(starknet_sign(&private_key, &[0xff; 32]).is_err());
+    }
+
+    #[test]
+    fn verifier_takes_transaction_hash() {
+        let mut private_key = [7u8; 32];
+        private_key[0] = 0;
+        let pubkey = starknet_public_key(&private_key).unwrap();
+        let hash = invoke_v3().transaction_hash(STARKNET_SN_MAIN).to_bytes_be();
+        let sig = starknet_sign(&private_key, &hash).unwrap();
+
+        assert_eq!(StarknetVerifier.verify(SignatureScheme::Stark, &pubkey, &hash, &sig), Ok(()));
+        assert_eq!(
+            StarknetVerifier.verify(SignatureScheme::Ecdsa, &pubkey, &hash, &sig),
+            Err(VerifyError::UnsupportedEngine),
+        );
+        assert_eq!(StarknetVerifier.signing_hashes(SignatureScheme::Stark, &hash), Ok(vec![hash.to_vec()]));
+        assert_eq!(
+            StarknetVerifier.signing_hashes(SignatureScheme::Stark, &hash[1..]),
+            Err(VerifyError::InvalidMessage),
+        );
+    }
+}
@@ -0,0 +1,320 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    ed25519_verify, inner_ecdsa_verify_with_policy, secp256r1_verify_prehash, Hash256, LowSPolicy, VerifyError,
+};
+
+/// Sui verifies secp256k1 and secp256r1 signatures in low-S form only.
+pub const SUI_LOW_S_POLICY: LowSPolicy = LowSPolicy::Strict;
+
+/// What a signed intent message holds, the first byte of the intent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SuiIntentScope {
+    TransactionData = 0,
+    TransactionEffects = 1,
+    CheckpointSummary = 2,
+    PersonalMessage = 3,
+}
+
+/// `scope || version || app_id` prefix of every message a Sui key signs, so a signature
+/// over one kind of value is never valid for another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuiIntent {
+    pub scope: SuiIntentScope,
+    pub version: u8,
+    pub app_id: u8,
+}
+
+impl SuiIntent {
+    /// Intent of a Sui `TransactionData`, version 0 and app id 0 (Sui).
+    pub const fn sui_transaction() -> Self {
+        Self { scope: SuiIntentScope::TransactionData, version: 0, app_id: 0 }
+    }
+
+    /// Intent of a personal message, signed as the BCS encoding of its bytes.
+    pub const fn personal_message() -> Self {
+        Self { scope: SuiIntentScope::PersonalMessage, version: 0, app_id: 0 }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 3] {
+        [self.scope as u8, self.version, self.app_id]
+    }
+}
+
+/// Intent message of the BCS encoded `value`.
+pub fn sui_intent_message(intent: SuiIntent, value: &[u8]) -> Vec<u8> {
+    let mut msg = intent.to_bytes().to_vec();
+    msg.extend_from_slice(value);
+    msg
+}
+
+/// blake2b-256 of the intent message, the bytes every signature scheme signs.
+pub fn sui_intent_digest(intent: SuiIntent, value: &[u8]) -> [u8; 32] {
+    sp_io::hashing::blake2_256(&sui_intent_message(intent, value))
+}
+
+/// Digest identifying a BCS encoded `TransactionData` on chain, not the one signed.
+pub fn sui_transaction_digest(tx_data: &[u8]) -> [u8; 32] {
+    let mut preimage = b"TransactionData::".to_vec();
+    preimage.extend_from_slice(tx_data);
+    sp_io::hashing::blake2_256(&preimage)
+}
+
+/// Key schemes of single signer Sui signatures, by their flag byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SuiSignatureScheme {
+    Ed25519 = 0,
+    Secp256k1 = 1,
+    Secp256r1 = 2,
+}
+
+impl SuiSignatureScheme {
+    pub fn from_flag(flag: u8) -> Option<Self> {
+        match flag {
+            0 => Some(Self::Ed25519),
+            1 => Some(Self::Secp256k1),
+            2 => Some(Self::Secp256r1),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Length of the public key, compressed for the ecdsa schemes.
+    pub fn pubkey_len(&self) -> usize {
+        match self {
+            Self::Ed25519 => 32,
+            Self::Secp256k1 | Self::Secp256r1 => 33,
+        }
+    }
+}
+
+/// Address of a single key account, `blake2b_256(flag || pubkey)`.
+pub fn sui_address(scheme: SuiSignatureScheme, pubkey: &[u8]) -> [u8; 32] {
+    let mut preimage = vec![scheme.flag()];
+    preimage.extend_from_slice(pubkey);
+    sp_io::hashing::blake2_256(&preimage)
+}
+
+/// Serialized single signer signature, `flag || signature || pubkey`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuiSignature {
+    pub scheme: SuiSignatureScheme,
+    pub signature: [u8; 64],
+    pub pubkey: Vec<u8>,
+}
+
+impl SuiSignature {
+    /// Parse a flag prefixed signature, multisig and zkLogin flags are unsupported.
+    pub fn parse(bytes: &[u8]) -> Result<Self, VerifyError> {
+        let (&flag, rest) = bytes
+            .split_first()
+            .ok_or(VerifyError::InvalidSignatureLength { expected: 97, actual: 0 })?;
+        let scheme = SuiSignatureScheme::from_flag(flag).ok_or(VerifyError::UnsupportedEngine)?;
+        let expected = 1 + 64 + scheme.pubkey_len();
+        if bytes.len() != expected {
+            return Err(VerifyError::InvalidSignatureLength { expected: expected as u32, actual: bytes.len() as u32 });
+        }
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&rest[..64]);
+        Ok(Self { scheme, signature, pubkey: rest[64..].to_vec() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.scheme.flag()];
+        bytes.extend_from_slice(&self.signature);
+        bytes.extend_from_slice(&self.pubkey);
+        bytes
+    }
+
+    /// Address of the signer.
+    pub fn address(&self) -> [u8; 32] {
+        sui_address(self.scheme, &self.pubkey)
+    }
+
+    /// Verify against an intent digest, ed25519 signs the digest itself and the ecdsa
+    /// schemes its sha2_256.
+    pub fn verify_digest(&self, digest: &[u8; 32]) -> Result<(), VerifyError> {
+        match self.scheme {
+            SuiSignatureScheme::Ed25519 => ed25519_verify(&self.pubkey, digest, &self.signature),
+            SuiSignatureScheme::Secp256k1 => inner_ecdsa_verify_with_policy(
+                &self.pubkey,
+                digest,
+                &self.signature,
+                Some(Hash256::Sha2_256),
+                |v| v,
+                SUI_LOW_S_POLICY,
+            ),
+            SuiSignatureScheme::Secp256r1 => secp256r1_verify_prehash(
+                &self.pubkey,
+                &sp_io::hashing::sha2_256(digest),
+                &self.signature,
+                SUI_LOW_S_POLICY,
+            ),
+        }
+    }
+}
+
+/// Verify a flag prefixed signature of `pubkey` over a BCS encoded `TransactionData`.
+pub fn verify_sui(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    let signature = SuiSignature::parse(sig)?;
+    if signature.pubkey != pubkey {
+        return Err(VerifyError::BadSignature);
+    }
+    signature.verify_digest(&sui_intent_digest(SuiIntent::sui_transaction(), msg))
+}
+
+/// [`ChainVerifier`] of Sui transactions, the scheme chosen by the signature flag.
+pub struct SuiVerifier;
+
+impl SuiVerifier {
+    fn sui_scheme(scheme: SignatureScheme) -> Result<SuiSignatureScheme, VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => Ok(SuiSignatureScheme::Ed25519),
+            SignatureScheme::Ecdsa => Ok(SuiSignatureScheme::Secp256k1),
+            SignatureScheme::Secp256r1 => Ok(SuiSignatureScheme::Secp256r1),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+impl ChainVerifier for SuiVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        let digest = sui_intent_digest(SuiIntent::sui_transaction(), msg);
+        Ok(vec![match Self::sui_scheme(scheme)? {
+            SuiSignatureScheme::Ed25519 => digest.to_vec(),
+            SuiSignatureScheme::Secp256k1 | SuiSignatureScheme::Secp256r1 => sp_io::hashing::sha2_256(&digest).to_vec(),
+        }])
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        let expected = Self::sui_scheme(scheme)?;
+        if sig.first() != Some(&expected.flag()) {
+            return Err(VerifyError::InvalidSignature);
+        }
+        verify_sui(pubkey, msg, sig)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // signed with the ed25519 seed [0x01; 32] and the ecdsa secret keys [0x07; 32] by an
+    // independent implementation, over the placeholder transaction bytes 0x00..0x27
+    const ED25519: &str = "00fbdb7aa0ca31201cdcc6f84e49a5d761242fc3bad2aaa6932d09f3243b9706ecc5ad57a65b1b3548d74bb09ede834f7c2282490b6dab0f07ced69302d10538048a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+    const ED25519_ADDRESS: &str = "29dfbf688abce7ab43bb8e70cae158ae961196e721440f515482f8ba1684390f";
+    const SECP256K1: &str = "01a02c68650966692b977c1c911e1974c07bdee772cb271a39c440a9e09a9ed9a01c9300fbdaf4482f9a3aad7fd67513a97051f553383f23bccfa4fa34f3dd919b02989c0b76cb563971fdc9bef31ec06c3560f3249d6ee9e5d83c57625596e05f6f";
+    const SECP256K1_HIGH_S: &str = "01a02c68650966692b977c1c911e1974c07bdee772cb271a39c440a9e09a9ed9a0e36cff04250bb7d065c55280298aec554a5ce79377097c7ef02d6457dc58afa602989c0b76cb563971fdc9bef31ec06c3560f3249d6ee9e5d83c57625596e05f6f";
+    const SECP256K1_ADDRESS: &str = "3334442090548419b94695cbb1838652bb0494b54ff855494dd83491a3cfb6a6";
+    const SECP256R1: &str = "021705adcb7ab57da6c99340872dbf9eaa27ec48055d4e6d81635d6a83584ff1784f03f222962261bf973883997e379ee1862ca6250cd82ecc506030f01936b8c1031e18532fd4754c02f3041d9c75ceb33b83ffd81ac7ce4fe882ccb1c98bc5896e";
+    const SECP256R1_HIGH_S: &str = "021705adcb7ab57da6c99340872dbf9eaa27ec48055d4e6d81635d6a83584ff178b0fc0ddc69dd9e4168c77c6681c8611e36ba54889a3f6fb8a35999d2e32c6c90031e18532fd4754c02f3041d9c75ceb33b83ffd81ac7ce4fe882ccb1c98bc5896e";
+    const SECP256R1_ADDRESS: &str = "a75a3c31148891f7cc33e8f8b9069b710979a417a8594ace613ac5161219e93f";
+
+    fn tx_data() -> Vec<u8> {
+        (0..40).collect()
+    }
+
+    #[test]
+    fn intent_digests() {
+        assert_eq!(SuiIntent::sui_transaction().to_bytes(), [0, 0, 0]);
+        assert_eq!(SuiIntent::personal_message().to_bytes(), [3, 0, 0]);
+        assert_eq!(sui_intent_message(SuiIntent::sui_transaction(), &[9]), vec![0, 0, 0, 9]);
+        assert_eq!(
+            hex::encode(sui_intent_digest(SuiIntent::sui_transaction(), &tx_data())),
+            "490e078e4dc28d6fc9f572a4a079b010f52ce005c46f08bbe92fbe8fe2adb662",
+        );
+        assert_eq!(
+            hex::encode(sui_transaction_digest(&tx_data())),
+            "6f686b9a23a617c4eb729fac55b2d8176551d41a3418fdcd4f25e49333b82b44",
+        );
+    }
+
+    #[test]
+    fn parse_signatures_and_derive_addresses() {
+        for (sig, scheme, address) in [
+            (ED25519, SuiSignatureScheme::Ed25519, ED25519_ADDRESS),
+            (SECP256K1, SuiSignatureScheme::Secp256k1, SECP256K1_ADDRESS),
+            (SECP256R1, SuiSignatureScheme::Secp256r1, SECP256R1_ADDRESS),
+        ] {
+            let bytes = hex::decode(sig).unwrap();
+            let signature = SuiSignature::parse(&bytes).unwrap();
+            assert_eq!(signature.scheme, scheme);
+            assert_eq!(signature.pubkey.len(), scheme.pubkey_len());
+            assert_eq!(hex::encode(signature.address()), address);
+            assert_eq!(signature.to_bytes(), bytes);
+            assert_eq!(
+                SuiSignature::parse(&bytes[..bytes.len() - 1]),
+                Err(VerifyError::InvalidSignatureLength { expected: bytes.len() as u32, actual: bytes.len() as u32 - 1 }),
+            );
+        }
+        assert_eq!(SuiSignature::parse(&[]), Err(VerifyError::InvalidSignatureLength { expected: 97, actual: 0 }));
+        // multisig
+        assert_eq!(SuiSignature::parse(&[3; 98]), Err(VerifyError::UnsupportedEngine));
+    }
+
+    #[test]
+    fn verify_signed_transactions() {
+        for sig in [ED25519, SECP256K1, SECP256R1] {
+            let sig = hex::decode(sig).unwrap();
+            let pubkey = SuiSignature::parse(&sig).unwrap().pubkey;
+            assert_eq!(verify_sui(&pubkey, &tx_data(), &sig), Ok(()));
+            assert_eq!(verify_sui(&pubkey, &tx_data()[1..], &sig), Err(VerifyError::BadSignature));
+            assert_eq!(verify_sui(&[0u8; 33], &tx_data(), &sig), Err(VerifyError::BadSignature));
+
+            // a personal message signature can't pass for a transaction
+            let signature = SuiSignature::parse(&sig).unwrap();
+            assert_eq!(
+                signature.verify_digest(&sui_intent_digest(SuiIntent::personal_message(), &tx_data())),
+                Err(VerifyError::BadSignature),
+            );
+        }
+        for sig in [SECP256K1_HIGH_S, SECP256R1_HIGH_S] {
+            let sig = hex::decode(sig).unwrap();
+            let pubkey = SuiSignature::parse(&sig).unwrap().pubkey;
+            assert_eq!(verify_sui(&pubkey, &tx_data(), &sig), Err(VerifyError::HighS));
+        }
+    }
+
+    #[test]
+    fn verifier_matches_scheme_to_flag() {
+        let sig = hex::decode(SECP256K1).unwrap();
+        let pubkey = SuiSignature::parse(&sig).unwrap().pubkey;
+        assert_eq!(SuiVerifier.verify(SignatureScheme::Ecdsa, &pubkey, &tx_data(), &sig), Ok(()));
+        assert_eq!(
+            SuiVerifier.verify(SignatureScheme::Ed25519, &pubkey, &tx_data(), &sig),
+            Err(VerifyError::InvalidSignature),
+        );
+        assert_eq!(
+            SuiVerifier.verify(SignatureScheme::Sr25519, &pubkey, &tx_data(), &sig),
+            Err(VerifyError::UnsupportedEngine),
+        );
+        let digest = sui_intent_digest(SuiIntent::sui_transaction(), &tx_data());
+        assert_eq!(SuiVerifier.signing_hashes(SignatureScheme::Ed25519, &tx_data()), Ok(vec![digest.to_vec()]));
+        assert_eq!(
+            SuiVerifier.signing_hashes(SignatureScheme::Secp256r1, &tx_data()),
+            Ok(vec![sp_io::hashing::sha2_256(&digest).to_vec()]),
+        );
+    }
+}
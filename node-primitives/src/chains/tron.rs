@@ -15,14 +15,56 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::{vec::Vec, string::String};
-use crate::{crypto::inner_ecdsa_verify, Hash256};
+use sp_core::bounded::alloc::{vec, vec::Vec};
+use crate::{
+    chains::{ChainVerifier, SignatureScheme},
+    crypto::{inner_ecdsa_recover_pubkey_with_policy, inner_ecdsa_verify_with_policy},
+    sha2_256, Hash256, LowSPolicy, PubkeyFormat, VerifyError,
+};
+
+/// Signatures are checked by TVM contracts, which reject high-S like their EVM counterparts.
+pub const TRON_LOW_S_POLICY: LowSPolicy = LowSPolicy::Strict;
 
 pub fn to_tron_signed_message_hash(msg: Vec<u8>) -> Vec<u8> {
     chain_bridge::utils::to_eth_signed_message_hash(&msg, sp_io::hashing::keccak_256)
 }
 
 /// Verify tron ecdsa signature(sha2_256)
-pub fn tron_ecdsa_verify(pubkey: &[u8], msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Result<(), String> {
-    inner_ecdsa_verify(pubkey, msg, sig, hash256, to_tron_signed_message_hash)
+pub fn tron_ecdsa_verify(pubkey: &[u8], msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Result<(), VerifyError> {
+    inner_ecdsa_verify_with_policy(pubkey, msg, sig, hash256, to_tron_signed_message_hash, TRON_LOW_S_POLICY)
+}
+
+/// [`ChainVerifier`] of Tron, ECDSA over the signed message digest of [`tron_ecdsa_verify`].
+pub struct TronVerifier;
+
+impl ChainVerifier for TronVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => Ok(vec![to_tron_signed_message_hash(sha2_256(msg).to_vec())]),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => tron_ecdsa_verify(pubkey, msg, sig, None),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn recover(&self, scheme: SignatureScheme, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => {
+                inner_ecdsa_recover_pubkey_with_policy(
+                    msg,
+                    sig,
+                    None,
+                    to_tron_signed_message_hash,
+                    PubkeyFormat::Compressed,
+                    TRON_LOW_S_POLICY,
+                )
+            }
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
 }
@@ -0,0 +1,444 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chain_bridge::chain::ChainType;
+use codec::{Decode, Encode};
+use sp_core::bounded::alloc::{boxed::Box, vec, vec::Vec};
+use crate::{
+    bls_verify, btc_schnorr_verify,
+    chains::{
+        starknet_verify, AptosVerifier, BtcVerifier, CkbVerifier, EthVerifier, FilecoinVerifier, SolanaVerifier,
+        StarknetVerifier, SuiVerifier, TronVerifier,
+    },
+    ecdsa_recover_pubkey, ecdsa_verify, ed25519_verify, secp256r1_verify, sha2_256, sr25519_verify, sr_secp256k1_verify,
+    PubkeyFormat, VerifyError,
+};
+
+/// Signature scheme a message was signed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum SignatureScheme {
+    /// secp256k1 ECDSA.
+    Ecdsa,
+    /// BIP340 Schnorr over secp256k1.
+    Schnorr,
+    Ed25519,
+    Sr25519,
+    /// Schnorr over secp256k1 with a compressed or full public key and `s || R` signature.
+    SrSecp256k1,
+    /// BLS12-381 with public keys in G1, Filecoin ciphersuite.
+    Bls,
+    /// NIST P-256 ECDSA.
+    Secp256r1,
+    /// ECDSA over the STARK curve, signing a 32-byte field element hash.
+    Stark,
+}
+
+/// Signature verification of the messages one chain signs.
+///
+/// Every method takes the message and signature in the chain's own encoding, schemes the
+/// chain doesn't sign with fail with `UnsupportedEngine`.
+pub trait ChainVerifier: Send + Sync {
+    /// Decode `msg` into the digests signed with `scheme`, in signature order.
+    ///
+    /// Schemes signing the message itself, such as ed25519, return `msg` as the only entry.
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError>;
+
+    /// Verify the signature, or one signature per signing hash, over `msg`.
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError>;
+
+    /// Recover the compressed public key that signed `msg`, for schemes that allow it.
+    fn recover(&self, scheme: SignatureScheme, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        let _ = (scheme, msg, sig);
+        Err(VerifyError::UnsupportedEngine)
+    }
+}
+
+impl<T: ChainVerifier + ?Sized> ChainVerifier for &T {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        (**self).signing_hashes(scheme, msg)
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        (**self).verify(scheme, pubkey, msg, sig)
+    }
+
+    fn recover(&self, scheme: SignatureScheme, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        (**self).recover(scheme, msg, sig)
+    }
+}
+
+/// Plain curve verifiers for `ChainType::Raw`, ECDSA hashing the message with sha2_256.
+pub struct RawVerifier;
+
+impl ChainVerifier for RawVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        Ok(match scheme {
+            SignatureScheme::Ecdsa | SignatureScheme::Secp256r1 => vec![sha2_256(msg).to_vec()],
+            _ => vec![msg.to_vec()],
+        })
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => ecdsa_verify(pubkey, msg, sig, None),
+            SignatureScheme::Schnorr => btc_schnorr_verify(pubkey, msg, sig),
+            SignatureScheme::Ed25519 => ed25519_verify(pubkey, msg, sig),
+            SignatureScheme::Sr25519 => sr25519_verify(pubkey, msg, sig),
+            SignatureScheme::SrSecp256k1 => sr_secp256k1_verify(pubkey, msg, sig),
+            SignatureScheme::Bls => bls_verify(pubkey, msg, sig),
+            SignatureScheme::Secp256r1 => secp256r1_verify(pubkey, msg, sig),
+            SignatureScheme::Stark => starknet_verify(pubkey, msg, sig),
+        }
+    }
+
+    fn recover(&self, scheme: SignatureScheme, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ecdsa => ecdsa_recover_pubkey(msg, sig, None, PubkeyFormat::Compressed),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+/// Substrate extrinsic signers, ed25519 or sr25519 over the signing payload.
+pub struct SubstrateVerifier;
+
+impl ChainVerifier for SubstrateVerifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 | SignatureScheme::Sr25519 => Ok(vec![msg.to_vec()]),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => ed25519_verify(pubkey, msg, sig),
+            SignatureScheme::Sr25519 => sr25519_verify(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+/// Chains signing the message bytes with ed25519 as is.
+pub struct Ed25519Verifier;
+
+impl ChainVerifier for Ed25519Verifier {
+    fn signing_hashes(&self, scheme: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => Ok(vec![msg.to_vec()]),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+
+    fn verify(&self, scheme: SignatureScheme, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+        match scheme {
+            SignatureScheme::Ed25519 => ed25519_verify(pubkey, msg, sig),
+            _ => Err(VerifyError::UnsupportedEngine),
+        }
+    }
+}
+
+/// Verifier this crate ships for `chain`, if any.
+pub fn builtin_chain_verifier(chain: &ChainType) -> Option<&'static dyn ChainVerifier> {
+    Some(match chain {
+        ChainType::Raw => &RawVerifier,
+        ChainType::Fil => &FilecoinVerifier,
+        ChainType::Eth | ChainType::Bsc => &EthVerifier,
+        ChainType::Btc => &BtcVerifier,
+        ChainType::Tron => &TronVerifier,
+        ChainType::Ckb => &CkbVerifier,
+        ChainType::Solana => &SolanaVerifier,
+        ChainType::Aptos => &AptosVerifier,
+        ChainType::Sui => &SuiVerifier,
+        ChainType::Substrate => &SubstrateVerifier,
+        ChainType::Near | ChainType::Ton => &Ed25519Verifier,
+        ChainType::Starknet => &StarknetVerifier,
+    })
+}
+
+/// Verifiers by chain type, the built-in ones extended or overridden with [`Self::register`].
+pub struct ChainVerifierRegistry {
+    verifiers: Vec<(ChainType, Box<dyn ChainVerifier>)>,
+}
+
+impl ChainVerifierRegistry {
+    /// Registry without any verifier.
+    pub fn empty() -> Self {
+        Self { verifiers: Vec::new() }
+    }
+
+    /// Register `verifier` for `chain`, returning the verifier it replaces.
+    pub fn register(&mut self, chain: ChainType, verifier: Box<dyn ChainVerifier>) -> Option<Box<dyn ChainVerifier>> {
+        match self.verifiers.iter_mut().find(|(c, _)| *c == chain) {
+            Some((_, registered)) => Some(core::mem::replace(registered, verifier)),
+            None => {
+                self.verifiers.push((chain, verifier));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, chain: &ChainType) -> Option<&dyn ChainVerifier> {
+        self.verifiers.iter().find(|(c, _)| c == chain).map(|(_, verifier)| verifier.as_ref())
+    }
+
+    /// Verify with the verifier registered for `chain`, `UnsupportedEngine` without one.
+    pub fn verify(
+        &self,
+        chain: &ChainType,
+        scheme: SignatureScheme,
+        pubkey: &[u8],
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<(), VerifyError> {
+        self.get(chain).ok_or(VerifyError::UnsupportedEngine)?.verify(scheme, pubkey, msg, sig)
+    }
+}
+
+impl Default for ChainVerifierRegistry {
+    /// Registry of the built-in verifiers.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        let chains = [
+            ChainType::Raw,
+            ChainType::Fil,
+            ChainType::Bsc,
+            ChainType::Btc,
+            ChainType::Eth,
+            ChainType::Solana,
+            ChainType::Aptos,
+            ChainType::Starknet,
+            ChainType::Sui,
+            ChainType::Substrate,
+            ChainType::Ton,
+            ChainType::Near,
+            ChainType::Tron,
+            ChainType::Ckb,
+        ];
+        for chain in chains {
+            if let Some(verifier) = builtin_chain_verifier(&chain) {
+                registry.register(chain, Box::new(verifier));
+            }
+        }
+        registry
+    }
+}
+
+/// Verify `sig` over `msg` with the built-in verifier of `chain` for `scheme`.
+///
+/// Arguments are passed through unchanged, so `msg` and `sig` follow the conventions of
+/// the chain verifier, e.g. the raw Filecoin message for `Fil` or the PSBT-derived sighash
+/// list for `Btc`. `Raw` exposes the plain curve verifiers, ECDSA hashing with sha2_256.
+/// Combinations without a verifier fail with `UnsupportedEngine`.
+pub fn verify_by_chain(
+    chain: ChainType,
+    scheme: SignatureScheme,
+    pubkey: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), VerifyError> {
+    builtin_chain_verifier(&chain).ok_or(VerifyError::UnsupportedEngine)?.verify(scheme, pubkey, msg, sig)
+}
+
+/// [`verify_by_chain`] for the chain type of `chain_id`, unknown ids resolve to `Raw`.
+pub fn verify_by_chain_id(
+    chain_id: u32,
+    scheme: SignatureScheme,
+    pubkey: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), VerifyError> {
+    verify_by_chain(ChainType::from_chain_id(chain_id), scheme, pubkey, msg, sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_bridge::chain::{ETHEREUM_MAINNET, NEAR_MAINNET};
+
+    // RFC 8032 section 7.1, test 1
+    const ED25519_PUBKEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const ED25519_SIG: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    fn ecdsa_sign(digest: &[u8], seckey: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+        let sk = secp256k1::SecretKey::parse(seckey).unwrap();
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(digest);
+        let (sig, _) = secp256k1::sign(&secp256k1::Message::parse(&hash), &sk);
+        let pk = secp256k1::PublicKey::from_secret_key(&sk);
+        (pk.serialize_compressed().to_vec(), sig.serialize().to_vec())
+    }
+
+    #[test]
+    fn routes_ecdsa_by_chain() {
+        let msg = b"verify by chain";
+        let digest = sp_io::hashing::sha2_256(msg);
+        let eth_digest = chain_bridge::utils::to_eth_signed_message_hash(&digest, sp_io::hashing::keccak_256);
+
+        let (pk, sig) = ecdsa_sign(&eth_digest, &[7u8; 32]);
+        assert_eq!(verify_by_chain(ChainType::Eth, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(verify_by_chain(ChainType::Bsc, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(verify_by_chain(ChainType::Tron, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(verify_by_chain_id(ETHEREUM_MAINNET, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(
+            verify_by_chain(ChainType::Raw, SignatureScheme::Ecdsa, &pk, msg, &sig),
+            Err(VerifyError::BadSignature),
+        );
+
+        let (pk, sig) = ecdsa_sign(&digest, &[7u8; 32]);
+        assert_eq!(verify_by_chain(ChainType::Raw, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(verify_by_chain_id(u32::MAX, SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(
+            verify_by_chain(ChainType::Eth, SignatureScheme::Ecdsa, &pk, msg, &sig),
+            Err(VerifyError::BadSignature),
+        );
+    }
+
+    #[test]
+    fn routes_ed25519_by_chain() {
+        let pk = hex::decode(ED25519_PUBKEY).unwrap();
+        let sig = hex::decode(ED25519_SIG).unwrap();
+        for chain in [
+            ChainType::Raw,
+            ChainType::Near,
+            ChainType::Ton,
+            ChainType::Substrate,
+        ] {
+            assert_eq!(verify_by_chain(chain, SignatureScheme::Ed25519, &pk, b"", &sig), Ok(()));
+        }
+        assert_eq!(verify_by_chain_id(NEAR_MAINNET, SignatureScheme::Ed25519, &pk, b"", &sig), Ok(()));
+        assert_eq!(
+            verify_by_chain(ChainType::Near, SignatureScheme::Ed25519, &pk, b"x", &sig),
+            Err(VerifyError::BadSignature),
+        );
+        // solana and aptos messages are decoded before the signature is checked
+        for chain in [ChainType::Solana, ChainType::Aptos] {
+            assert_eq!(verify_by_chain(chain, SignatureScheme::Ed25519, &pk, b"", &sig), Err(VerifyError::InvalidMessage));
+        }
+        // sui signatures carry their flag and public key
+        assert_eq!(
+            verify_by_chain(ChainType::Sui, SignatureScheme::Ed25519, &pk, b"", &sig),
+            Err(VerifyError::InvalidSignature),
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_combinations() {
+        let pk = hex::decode(ED25519_PUBKEY).unwrap();
+        let sig = hex::decode(ED25519_SIG).unwrap();
+        for (chain, scheme) in [
+            (ChainType::Eth, SignatureScheme::Ed25519),
+            (ChainType::Solana, SignatureScheme::Ecdsa),
+            (ChainType::Btc, SignatureScheme::Bls),
+            (ChainType::Fil, SignatureScheme::Schnorr),
+            (ChainType::Tron, SignatureScheme::Schnorr),
+            (ChainType::Substrate, SignatureScheme::SrSecp256k1),
+            (ChainType::Starknet, SignatureScheme::Ecdsa),
+        ] {
+            assert_eq!(verify_by_chain(chain, scheme, &pk, b"", &sig), Err(VerifyError::UnsupportedEngine));
+        }
+    }
+
+    #[test]
+    fn recover_applies_chain_low_s_policy() {
+        let msg = b"recover by chain";
+        let digest = sp_io::hashing::sha2_256(msg);
+        let eth_digest = chain_bridge::utils::to_eth_signed_message_hash(&digest, sp_io::hashing::keccak_256);
+        let registry = ChainVerifierRegistry::default();
+        for (chain, digest, high_s) in [
+            (ChainType::Eth, eth_digest.clone(), Err(VerifyError::HighS)),
+            (ChainType::Tron, eth_digest, Err(VerifyError::HighS)),
+            (ChainType::Raw, digest.to_vec(), Ok(())),
+        ] {
+            let sk = secp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+            let pk = secp256k1::PublicKey::from_secret_key(&sk).serialize_compressed().to_vec();
+            let (mut signature, recovery_id) = secp256k1::sign(&secp256k1::Message::parse_slice(&digest).unwrap(), &sk);
+            let mut sig = signature.serialize().to_vec();
+            sig.push(recovery_id.serialize());
+            let verifier = registry.get(&chain).unwrap();
+            assert_eq!(verifier.recover(SignatureScheme::Ecdsa, msg, &sig), Ok(pk.clone()));
+
+            signature.s = -signature.s;
+            let mut sig = signature.serialize().to_vec();
+            sig.push(recovery_id.serialize() ^ 1);
+            assert_eq!(verifier.recover(SignatureScheme::Ecdsa, msg, &sig), high_s.map(|_| pk));
+        }
+    }
+
+    #[test]
+    fn builtin_verifiers_expose_signing_hashes_and_recover() {
+        let msg = b"verify by chain";
+        let digest = sp_io::hashing::sha2_256(msg);
+        let eth_digest = chain_bridge::utils::to_eth_signed_message_hash(&digest, sp_io::hashing::keccak_256);
+        let (pk, mut sig) = ecdsa_sign(&eth_digest, &[7u8; 32]);
+
+        let registry = ChainVerifierRegistry::default();
+        let eth = registry.get(&ChainType::Eth).unwrap();
+        assert_eq!(eth.signing_hashes(SignatureScheme::Ecdsa, msg), Ok(vec![eth_digest.clone()]));
+        assert_eq!(eth.verify(SignatureScheme::Ecdsa, &pk, msg, &sig), Ok(()));
+        assert_eq!(eth.recover(SignatureScheme::Ed25519, msg, &sig), Err(VerifyError::UnsupportedEngine));
+        let recovered = (0..2).map(|v| {
+            sig.truncate(64);
+            sig.push(v + 27);
+            eth.recover(SignatureScheme::Ecdsa, msg, &sig)
+        });
+        assert!(recovered.into_iter().any(|recovered| recovered == Ok(pk.clone())));
+
+        assert_eq!(
+            registry.get(&ChainType::Raw).unwrap().signing_hashes(SignatureScheme::Ecdsa, msg),
+            Ok(vec![digest.to_vec()]),
+        );
+        assert_eq!(
+            registry.get(&ChainType::Btc).unwrap().signing_hashes(SignatureScheme::Ecdsa, b"not a btc message"),
+            Err(VerifyError::InvalidMessage),
+        );
+        assert!(registry.get(&ChainType::Starknet).is_some());
+    }
+
+    struct AcceptAll;
+
+    impl ChainVerifier for AcceptAll {
+        fn signing_hashes(&self, _: SignatureScheme, msg: &[u8]) -> Result<Vec<Vec<u8>>, VerifyError> {
+            Ok(vec![msg.to_vec()])
+        }
+
+        fn verify(&self, _: SignatureScheme, _: &[u8], _: &[u8], _: &[u8]) -> Result<(), VerifyError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registry_accepts_downstream_verifiers() {
+        let mut registry = ChainVerifierRegistry::empty();
+        assert_eq!(
+            registry.verify(&ChainType::Starknet, SignatureScheme::Ecdsa, b"", b"", b""),
+            Err(VerifyError::UnsupportedEngine),
+        );
+        assert!(registry.register(ChainType::Starknet, Box::new(AcceptAll)).is_none());
+        assert_eq!(registry.verify(&ChainType::Starknet, SignatureScheme::Ecdsa, b"", b"", b""), Ok(()));
+
+        let mut registry = ChainVerifierRegistry::default();
+
+        assert_eq!(
+            registry.verify(&ChainType::Eth, SignatureScheme::Ecdsa, b"", b"", b""),
+            Err(VerifyError::InvalidSignatureLength { expected: 64, actual: 0 }),
+        );
+        assert!(registry.register(ChainType::Eth, Box::new(AcceptAll)).is_some());
+        assert_eq!(registry.verify(&ChainType::Eth, SignatureScheme::Ecdsa, b"", b"", b""), Ok(()));
+        assert!(ChainVerifierRegistry::empty().get(&ChainType::Raw).is_none());
+    }
+}
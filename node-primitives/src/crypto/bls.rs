@@ -15,17 +15,516 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::string::{String, ToString};
-use bls_signatures::{verify as verify_bls_sig, Serialize};
-
-pub fn bls_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
-    let pk = bls_signatures::PublicKey::from_bytes(&pubkey).map_err(|e| e.to_string())?;
-    // generate signature struct from bytes
-    let sig = bls_signatures::Signature::from_bytes(sig).map_err(|e| e.to_string())?;
-    let hashed = bls_signatures::hash(&msg);
-    // BLS verify hash against key
-    if !verify_bls_sig(&sig, &[hashed], &[pk]) {
-        return Err("bls signature verify failed".to_string());
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar};
+use bls_signatures::{PrivateKey, Serialize};
+use codec::{Decode, Encode};
+use sp_core::bounded::alloc::{string::{String, ToString}, vec::Vec};
+use crate::VerifyError;
+
+/// BLS12-381 signature variants. Min-pk variants use 48-byte G1 public keys and 96-byte G2
+/// signatures, min-sig the other way round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum BlsCiphersuite {
+    /// Filecoin, min-pk with `BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_`.
+    Filecoin,
+    /// Ethereum consensus layer, min-pk with `BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_`.
+    EthereumPop,
+    /// Min-sig with `BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_`.
+    MinSigPop,
+}
+
+impl BlsCiphersuite {
+    pub fn dst(&self) -> &'static [u8] {
+        match self {
+            BlsCiphersuite::Filecoin => b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_",
+            BlsCiphersuite::EthereumPop => b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_",
+            BlsCiphersuite::MinSigPop => b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_",
+        }
+    }
+
+    /// Domain separation tag of proofs of possession, the `_POP_` ciphersuite's
+    /// `BLS_POP_BLS12381G2_...` for min-pk keys and `BLS_POP_BLS12381G1_...` for min-sig keys.
+    /// Filecoin's basic scheme defines none, its keys are proven under the min-pk one.
+    pub fn pop_dst(&self) -> &'static [u8] {
+        if self.min_sig() {
+            b"BLS_POP_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_"
+        } else {
+            b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_"
+        }
+    }
+
+    fn min_sig(&self) -> bool {
+        matches!(self, BlsCiphersuite::MinSigPop)
+    }
+
+    pub fn pubkey_len(&self) -> usize {
+        if self.min_sig() { 96 } else { 48 }
+    }
+
+    pub fn signature_len(&self) -> usize {
+        if self.min_sig() { 48 } else { 96 }
+    }
+
+    fn hash(&self, msg: &[u8]) -> BlsPoint {
+        self.hash_with_dst(msg, self.dst())
+    }
+
+    fn pop_hash(&self, pubkey: &[u8]) -> BlsPoint {
+        self.hash_with_dst(pubkey, self.pop_dst())
+    }
+
+    fn hash_with_dst(&self, msg: &[u8], dst: &[u8]) -> BlsPoint {
+        if self.min_sig() {
+            BlsPoint::G1(<G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst))
+        } else {
+            BlsPoint::G2(<G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst))
+        }
+    }
+
+    // public key of `sk`, on the curve opposite to the signatures
+    fn public_key(&self, sk: Scalar) -> BlsPoint {
+        if self.min_sig() {
+            BlsPoint::G2(G2Projective::generator() * sk)
+        } else {
+            BlsPoint::G1(G1Projective::generator() * sk)
+        }
+    }
+}
+
+/// A public key or signature, on whichever curve the ciphersuite puts it.
+#[derive(Clone, Copy)]
+enum BlsPoint {
+    G1(G1Projective),
+    G2(G2Projective),
+}
+
+impl BlsPoint {
+    // compressed encoding, checked to be in the prime order subgroup
+    fn parse(bytes: &[u8], g1: bool) -> Option<Self> {
+        if g1 {
+            let bytes: &[u8; 48] = bytes.try_into().ok()?;
+            Option::<G1Affine>::from(G1Affine::from_compressed(bytes)).map(|p| BlsPoint::G1(p.into()))
+        } else {
+            let bytes: &[u8; 96] = bytes.try_into().ok()?;
+            Option::<G2Affine>::from(G2Affine::from_compressed(bytes)).map(|p| BlsPoint::G2(p.into()))
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        match self {
+            BlsPoint::G1(p) => G1Affine::from(p).to_compressed().to_vec(),
+            BlsPoint::G2(p) => G2Affine::from(p).to_compressed().to_vec(),
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        match self {
+            BlsPoint::G1(p) => bool::from(p.is_identity()),
+            BlsPoint::G2(p) => bool::from(p.is_identity()),
+        }
+    }
+
+    fn mul(self, scalar: Scalar) -> Self {
+        match self {
+            BlsPoint::G1(p) => BlsPoint::G1(p * scalar),
+            BlsPoint::G2(p) => BlsPoint::G2(p * scalar),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (BlsPoint::G1(a), BlsPoint::G1(b)) => BlsPoint::G1(a + b),
+            (BlsPoint::G2(a), BlsPoint::G2(b)) => BlsPoint::G2(a + b),
+            _ => unreachable!("points of one ciphersuite share a group; qed"),
+        }
+    }
+}
+
+/// `prod e(pk_i, H(m_i)) == e(G, sig)`, arguments swapped for min-sig.
+fn bls_pairing_check(pks: &[BlsPoint], hashes: &[BlsPoint], sig: BlsPoint) -> bool {
+    let mut pairs: Vec<(G1Affine, G2Prepared)> = Vec::with_capacity(pks.len() + 1);
+    for (pk, hash) in pks.iter().zip(hashes) {
+        match (*pk, *hash) {
+            (BlsPoint::G1(pk), BlsPoint::G2(h)) => pairs.push((pk.into(), G2Affine::from(h).into())),
+            (BlsPoint::G2(pk), BlsPoint::G1(h)) => pairs.push((h.into(), G2Affine::from(pk).into())),
+            _ => return false,
+        }
+    }
+    match sig {
+        BlsPoint::G2(sig) => pairs.push((-G1Affine::generator(), G2Affine::from(sig).into())),
+        BlsPoint::G1(sig) => pairs.push((sig.into(), (-G2Affine::generator()).into())),
+    }
+    let terms: Vec<(&G1Affine, &G2Prepared)> = pairs.iter().map(|(a, b)| (a, b)).collect();
+    multi_miller_loop(&terms).final_exponentiation() == Gt::identity()
+}
+
+// KeyValidate: a compressed point in the prime order subgroup, not the identity.
+fn parse_bls_pubkey(pubkey: &[u8], suite: BlsCiphersuite) -> Result<BlsPoint, VerifyError> {
+    if pubkey.len() != suite.pubkey_len() {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: suite.pubkey_len() as u32, actual: pubkey.len() as u32 });
+    }
+    match BlsPoint::parse(pubkey, !suite.min_sig()) {
+        Some(pk) if !pk.is_identity() => Ok(pk),
+        _ => Err(VerifyError::InvalidPublicKey),
+    }
+}
+
+fn parse_bls_signature(sig: &[u8], suite: BlsCiphersuite) -> Result<BlsPoint, VerifyError> {
+    if sig.len() != suite.signature_len() {
+        return Err(VerifyError::InvalidSignatureLength { expected: suite.signature_len() as u32, actual: sig.len() as u32 });
+    }
+    BlsPoint::parse(sig, suite.min_sig()).ok_or(VerifyError::InvalidSignature)
+}
+
+/// Verify a Filecoin BLS signature.
+pub fn bls_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    bls_verify_with_ciphersuite(pubkey, msg, sig, BlsCiphersuite::Filecoin)
+}
+
+pub fn bls_verify_with_ciphersuite(pubkey: &[u8], msg: &[u8], sig: &[u8], suite: BlsCiphersuite) -> Result<(), VerifyError> {
+    let pk = parse_bls_pubkey(pubkey, suite)?;
+    let sig = parse_bls_signature(sig, suite)?;
+    if !bls_pairing_check(&[pk], &[suite.hash(msg)], sig) {
+        return Err(VerifyError::BadSignature);
+    }
+    Ok(())
+}
+
+/// Sum of Filecoin BLS public keys, the key a fast-aggregate signature verifies against.
+pub fn bls_aggregate_pubkeys(pubkeys: &[&[u8]]) -> Result<Vec<u8>, VerifyError> {
+    bls_aggregate_pubkeys_with_ciphersuite(pubkeys, BlsCiphersuite::Filecoin)
+}
+
+pub fn bls_aggregate_pubkeys_with_ciphersuite(pubkeys: &[&[u8]], suite: BlsCiphersuite) -> Result<Vec<u8>, VerifyError> {
+    let mut pks = pubkeys.iter().enumerate().map(|(i, pubkey)| {
+        parse_bls_pubkey(pubkey, suite).map_err(|e| VerifyError::at_index(i, e))
+    });
+    let mut sum = pks.next().ok_or(VerifyError::InvalidPublicKey)??;
+    for pk in pks {
+        sum = sum.add(pk?);
+    }
+    if sum.is_identity() {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    Ok(sum.to_bytes())
+}
+
+/// Sum of Filecoin BLS signatures, over the same or distinct messages.
+pub fn bls_aggregate_signatures(sigs: &[&[u8]]) -> Result<Vec<u8>, VerifyError> {
+    bls_aggregate_signatures_with_ciphersuite(sigs, BlsCiphersuite::Filecoin)
+}
+
+pub fn bls_aggregate_signatures_with_ciphersuite(sigs: &[&[u8]], suite: BlsCiphersuite) -> Result<Vec<u8>, VerifyError> {
+    let mut parsed = sigs
+        .iter()
+        .enumerate()
+        .map(|(i, sig)| parse_bls_signature(sig, suite).map_err(|e| VerifyError::at_index(i, e)));
+    let mut sum = parsed.next().ok_or(VerifyError::InvalidSignature)??;
+    for sig in parsed {
+        sum = sum.add(sig?);
+    }
+    Ok(sum.to_bytes())
+}
+
+/// Verify a Filecoin aggregate signature where `pubkeys[i]` signed `msgs[i]`.
+///
+/// Messages must be pairwise distinct, which is what makes the scheme safe against rogue
+/// keys without proofs of possession; duplicates are rejected with `InvalidMessage`.
+pub fn bls_aggregate_verify(pubkeys: &[&[u8]], msgs: &[&[u8]], sig: &[u8]) -> Result<(), VerifyError> {
+    bls_aggregate_verify_with_ciphersuite(pubkeys, msgs, sig, BlsCiphersuite::Filecoin)
+}
+
+pub fn bls_aggregate_verify_with_ciphersuite(
+    pubkeys: &[&[u8]],
+    msgs: &[&[u8]],
+    sig: &[u8],
+    suite: BlsCiphersuite,
+) -> Result<(), VerifyError> {
+    if msgs.is_empty() || pubkeys.len() != msgs.len() {
+        return Err(VerifyError::InvalidMessage);
+    }
+    for (i, msg) in msgs.iter().enumerate() {
+        if msgs[..i].contains(msg) {
+            return Err(VerifyError::at_index(i, VerifyError::InvalidMessage));
+        }
+    }
+    let pks = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, pubkey)| parse_bls_pubkey(pubkey, suite).map_err(|e| VerifyError::at_index(i, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sig = parse_bls_signature(sig, suite)?;
+    let hashes: Vec<_> = msgs.iter().map(|msg| suite.hash(msg)).collect();
+    if !bls_pairing_check(&pks, &hashes, sig) {
+        return Err(VerifyError::BadSignature);
     }
     Ok(())
 }
+
+/// Verify a Filecoin signature of `msg` aggregated from every key in `pubkeys`, with one
+/// pairing check against the summed key.
+///
+/// Only safe for keys whose proof of possession was checked when they were registered, see
+/// [`bls_verify_key_registration`], otherwise a rogue key can forge a signature for the whole set.
+/// Filecoin's basic scheme has no proof of possession of its own, so its keys are registered
+/// with [`BlsCiphersuite::Filecoin`], which proves them under the min-pk `_POP_` tag. That tag
+/// differs from the `_NUL_` signing tag, so a proof is never also a signature.
+pub fn bls_fast_aggregate_verify(pubkeys: &[&[u8]], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    bls_fast_aggregate_verify_with_ciphersuite(pubkeys, msg, sig, BlsCiphersuite::Filecoin)
+}
+
+/// `FastAggregateVerify`, e.g. of beacon chain attestations with [`BlsCiphersuite::EthereumPop`].
+pub fn bls_fast_aggregate_verify_with_ciphersuite(
+    pubkeys: &[&[u8]],
+    msg: &[u8],
+    sig: &[u8],
+    suite: BlsCiphersuite,
+) -> Result<(), VerifyError> {
+    let aggregated = bls_aggregate_pubkeys_with_ciphersuite(pubkeys, suite)?;
+    bls_verify_with_ciphersuite(&aggregated, msg, sig, suite)
+}
+
+/// `PopProve`: sign the compressed public key of `seckey` under the ciphersuite's
+/// [`BlsCiphersuite::pop_dst`].
+pub fn bls_pop_prove(seckey: &[u8], suite: BlsCiphersuite) -> Result<Vec<u8>, String> {
+    let sk = PrivateKey::from_bytes(seckey).map_err(|_| "invalid bls secret key".to_string())?;
+    let scalar = Scalar::from(sk);
+    if scalar == Scalar::zero() {
+        return Err("invalid bls secret key".to_string());
+    }
+    let pubkey = suite.public_key(scalar).to_bytes();
+    Ok(suite.pop_hash(&pubkey).mul(scalar).to_bytes())
+}
+
+/// `PopVerify`: check `proof` shows knowledge of the secret key of `pubkey`.
+pub fn bls_pop_verify(pubkey: &[u8], proof: &[u8], suite: BlsCiphersuite) -> Result<(), VerifyError> {
+    let pk = parse_bls_pubkey(pubkey, suite)?;
+    let proof = parse_bls_signature(proof, suite)?;
+    // e(PK, H(PK)) == e(G, proof), arguments swapped for min-sig
+    if !bls_pairing_check(&[pk], &[suite.pop_hash(pubkey)], proof) {
+        return Err(VerifyError::BadSignature);
+    }
+    Ok(())
+}
+
+/// Registration time check of a committee member's BLS key: the key is valid, not already
+/// in `registered` and comes with a valid proof of possession. Only keys accepted here may
+/// be combined with [`bls_fast_aggregate_verify_with_ciphersuite`] of the same `suite`.
+pub fn bls_verify_key_registration(
+    pubkey: &[u8],
+    proof: &[u8],
+    registered: &[&[u8]],
+    suite: BlsCiphersuite,
+) -> Result<(), VerifyError> {
+    if registered.contains(&pubkey) {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    bls_pop_verify(pubkey, proof, suite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls_signatures::PublicKey;
+
+    fn key(i: u8) -> PrivateKey {
+        let mut seed = [0u8; 32];
+        seed[0] = i;
+        seed[1] = 0x5a;
+        PrivateKey::from_bytes(&seed).unwrap()
+    }
+
+    fn keys(count: u8) -> (Vec<PrivateKey>, Vec<Vec<u8>>) {
+        let keys: Vec<_> = (1..=count).map(key).collect();
+        let pubkeys = keys.iter().map(|k| k.public_key().as_bytes()).collect();
+        (keys, pubkeys)
+    }
+
+    fn refs(v: &[Vec<u8>]) -> Vec<&[u8]> {
+        v.iter().map(|x| &x[..]).collect()
+    }
+
+    #[test]
+    fn aggregate_verify_distinct_messages() {
+        let (keys, pubkeys) = keys(4);
+        let msgs: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 40]).collect();
+        let sigs: Vec<_> = keys.iter().zip(&msgs).map(|(k, m)| k.sign(m).as_bytes()).collect();
+        let sig = bls_aggregate_signatures(&refs(&sigs)).unwrap();
+        assert_eq!(bls_aggregate_verify(&refs(&pubkeys), &refs(&msgs), &sig), Ok(()));
+
+        let mut swapped = refs(&msgs);
+        swapped.swap(0, 1);
+        assert_eq!(bls_aggregate_verify(&refs(&pubkeys), &swapped, &sig), Err(VerifyError::BadSignature));
+        let mut duplicate = refs(&msgs);
+        duplicate[3] = duplicate[1];
+        assert_eq!(
+            bls_aggregate_verify(&refs(&pubkeys), &duplicate, &sig),
+            Err(VerifyError::at_index(3, VerifyError::InvalidMessage))
+        );
+        assert_eq!(bls_aggregate_verify(&refs(&pubkeys[1..]), &refs(&msgs), &sig), Err(VerifyError::InvalidMessage));
+    }
+
+    #[test]
+    fn fast_aggregate_verify_same_message() {
+        let (keys, pubkeys) = keys(5);
+        let msg = b"committee attestation";
+        let sigs: Vec<_> = keys.iter().map(|k| k.sign(msg).as_bytes()).collect();
+        let sig = bls_aggregate_signatures(&refs(&sigs)).unwrap();
+        assert_eq!(bls_fast_aggregate_verify(&refs(&pubkeys), msg, &sig), Ok(()));
+        assert_eq!(bls_fast_aggregate_verify(&refs(&pubkeys[..4]), msg, &sig), Err(VerifyError::BadSignature));
+        assert_eq!(bls_fast_aggregate_verify(&refs(&pubkeys), b"other", &sig), Err(VerifyError::BadSignature));
+
+        let aggregated = bls_aggregate_pubkeys(&refs(&pubkeys)).unwrap();
+        assert_eq!(bls_verify(&aggregated, msg, &sig), Ok(()));
+    }
+
+    #[test]
+    fn rejects_identity_and_malformed_inputs() {
+        let (_, pubkeys) = keys(2);
+        // compressed encoding of the G1 identity
+        let mut identity = vec![0u8; 48];
+        identity[0] = 0xc0;
+        assert_eq!(
+            bls_aggregate_pubkeys(&[&pubkeys[0], &identity]),
+            Err(VerifyError::at_index(1, VerifyError::InvalidPublicKey))
+        );
+        assert_eq!(bls_aggregate_pubkeys(&[]), Err(VerifyError::InvalidPublicKey));
+        assert_eq!(
+            bls_aggregate_signatures(&[&[0u8; 95]]),
+            Err(VerifyError::at_index(0, VerifyError::InvalidSignatureLength { expected: 96, actual: 95 }))
+        );
+        assert_eq!(bls_aggregate_signatures(&[]), Err(VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn proof_of_possession() {
+        let suites = [BlsCiphersuite::Filecoin, BlsCiphersuite::EthereumPop, BlsCiphersuite::MinSigPop];
+        let (keys, _) = keys(3);
+        for suite in suites {
+            let pubkeys: Vec<_> = keys.iter().map(|k| suite.public_key(Scalar::from(*k)).to_bytes()).collect();
+            let proofs: Vec<_> = keys.iter().map(|k| bls_pop_prove(&k.as_bytes(), suite).unwrap()).collect();
+            assert_eq!(pubkeys[0].len(), suite.pubkey_len());
+            assert_eq!(proofs[0].len(), suite.signature_len());
+            for (pubkey, proof) in pubkeys.iter().zip(&proofs) {
+                assert_eq!(bls_pop_verify(pubkey, proof, suite), Ok(()));
+            }
+            assert_eq!(bls_pop_verify(&pubkeys[0], &proofs[1], suite), Err(VerifyError::BadSignature));
+            // a plain signature over the key bytes is not a proof, the DSTs differ
+            let signed = suite.hash(&pubkeys[0]).mul(Scalar::from(keys[0])).to_bytes();
+            assert_eq!(bls_pop_verify(&pubkeys[0], &signed, suite), Err(VerifyError::BadSignature));
+            assert!(bls_pop_prove(&[0u8; 32], suite).is_err());
+        }
+        assert_eq!(BlsCiphersuite::Filecoin.pop_dst(), BlsCiphersuite::EthereumPop.pop_dst());
+        assert_eq!(BlsCiphersuite::MinSigPop.pop_dst(), b"BLS_POP_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_");
+    }
+
+    #[test]
+    fn key_registration_rejects_rogue_keys() {
+        let (keys, pubkeys) = keys(3);
+        let proof = bls_pop_prove(&keys[2].as_bytes(), BlsCiphersuite::Filecoin).unwrap();
+        assert_eq!(bls_verify_key_registration(&pubkeys[2], &proof, &refs(&pubkeys[..2]), BlsCiphersuite::Filecoin), Ok(()));
+        assert_eq!(
+            bls_verify_key_registration(&pubkeys[2], &proof, &refs(&pubkeys), BlsCiphersuite::Filecoin),
+            Err(VerifyError::InvalidPublicKey)
+        );
+
+        // rogue key PK_r = X - PK_0 lets the attacker alone sign for {PK_0, PK_r}, it has no proof
+        let attacker = key(9);
+        let rogue = G1Projective::from(attacker.public_key()) - G1Projective::from(PublicKey::from_bytes(&pubkeys[0]).unwrap());
+        let rogue = PublicKey::from(rogue).as_bytes();
+        let forged = attacker.sign(b"msg").as_bytes();
+        assert_eq!(bls_fast_aggregate_verify(&[&pubkeys[0], &rogue], b"msg", &forged), Ok(()));
+        let rogue_proof = bls_pop_prove(&attacker.as_bytes(), BlsCiphersuite::Filecoin).unwrap();
+        assert_eq!(
+            bls_verify_key_registration(&rogue, &rogue_proof, &refs(&pubkeys), BlsCiphersuite::Filecoin),
+            Err(VerifyError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn min_sig_key_registration_rejects_rogue_keys() {
+        let suite = BlsCiphersuite::MinSigPop;
+        let (keys, _) = keys(2);
+        let pubkey = suite.public_key(Scalar::from(keys[0])).to_bytes();
+        let proof = bls_pop_prove(&keys[0].as_bytes(), suite).unwrap();
+        assert_eq!(bls_verify_key_registration(&pubkey, &proof, &[], suite), Ok(()));
+        // a min-pk proof of the same secret does not register a min-sig key
+        let min_pk_proof = bls_pop_prove(&keys[0].as_bytes(), BlsCiphersuite::EthereumPop).unwrap();
+        assert!(bls_verify_key_registration(&pubkey, &min_pk_proof, &[], suite).is_err());
+
+        let attacker = Scalar::from(keys[1]);
+        let rogue = match (suite.public_key(attacker), BlsPoint::parse(&pubkey, false).unwrap()) {
+            (BlsPoint::G2(x), BlsPoint::G2(pk)) => BlsPoint::G2(x - pk).to_bytes(),
+            _ => unreachable!(),
+        };
+        let forged = suite.hash(b"msg").mul(attacker).to_bytes();
+        assert_eq!(bls_fast_aggregate_verify_with_ciphersuite(&[&pubkey, &rogue], b"msg", &forged, suite), Ok(()));
+        let rogue_proof = bls_pop_prove(&keys[1].as_bytes(), suite).unwrap();
+        assert_eq!(
+            bls_verify_key_registration(&rogue, &rogue_proof, &[&pubkey], suite),
+            Err(VerifyError::BadSignature)
+        );
+    }
+
+    /// consensus-spec-tests `bls/sign/small/sign_case_84d45c9c7cca6b92`
+    #[test]
+    fn ethereum_consensus_vector() {
+        let pubkey = hex::decode(
+            "a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a",
+        )
+        .unwrap();
+        let sig = hex::decode(
+            "b6ed936746e01f8ecf281f020953fbf1f01debd5657c4a383940b020b26507f6076334f91e2366c96e9ab279fb5158090352ea1c\
+             5b0c9274504f4f0e7053af24802e51e4568d164fe986834f41e55c8e850ce1f98458c0cfc9ab380b55285a55",
+        )
+        .unwrap();
+        let msg = [0u8; 32];
+        assert_eq!(bls_verify_with_ciphersuite(&pubkey, &msg, &sig, BlsCiphersuite::EthereumPop), Ok(()));
+        assert_eq!(bls_verify_with_ciphersuite(&pubkey, &msg, &sig, BlsCiphersuite::Filecoin), Err(VerifyError::BadSignature));
+        assert_eq!(bls_verify(&pubkey, &msg, &sig), Err(VerifyError::BadSignature));
+    }
+
+    fn sign(suite: BlsCiphersuite, i: u64, msg: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let sk = Scalar::from(i * 7919 + 1);
+        match suite.hash(msg) {
+            BlsPoint::G2(h) => (BlsPoint::G1(G1Projective::generator() * sk).to_bytes(), BlsPoint::G2(h * sk).to_bytes()),
+            BlsPoint::G1(h) => (BlsPoint::G2(G2Projective::generator() * sk).to_bytes(), BlsPoint::G1(h * sk).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn ciphersuites_are_separated() {
+        let suites = [BlsCiphersuite::Filecoin, BlsCiphersuite::EthereumPop, BlsCiphersuite::MinSigPop];
+        let msg = b"attestation data root";
+        for suite in suites {
+            let signed: Vec<_> = (0..4).map(|i| sign(suite, i, msg)).collect();
+            let pubkeys: Vec<&[u8]> = signed.iter().map(|(pk, _)| &pk[..]).collect();
+            let sigs: Vec<&[u8]> = signed.iter().map(|(_, sig)| &sig[..]).collect();
+            assert_eq!(pubkeys[0].len(), suite.pubkey_len());
+            assert_eq!(sigs[0].len(), suite.signature_len());
+            assert_eq!(bls_verify_with_ciphersuite(pubkeys[0], msg, sigs[0], suite), Ok(()));
+
+            let aggregated = bls_aggregate_signatures_with_ciphersuite(&sigs, suite).unwrap();
+            assert_eq!(bls_fast_aggregate_verify_with_ciphersuite(&pubkeys, msg, &aggregated, suite), Ok(()));
+            assert_eq!(
+                bls_fast_aggregate_verify_with_ciphersuite(&pubkeys[1..], msg, &aggregated, suite),
+                Err(VerifyError::BadSignature)
+            );
+
+            for other in suites.into_iter().filter(|other| *other != suite) {
+                assert!(bls_verify_with_ciphersuite(pubkeys[0], msg, sigs[0], other).is_err());
+            }
+        }
+
+        let msgs: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 8]).collect();
+        let signed: Vec<_> = msgs.iter().zip(0..).map(|(m, i)| sign(BlsCiphersuite::MinSigPop, i, m)).collect();
+        let pubkeys: Vec<&[u8]> = signed.iter().map(|(pk, _)| &pk[..]).collect();
+        let sigs: Vec<&[u8]> = signed.iter().map(|(_, sig)| &sig[..]).collect();
+        let aggregated = bls_aggregate_signatures_with_ciphersuite(&sigs, BlsCiphersuite::MinSigPop).unwrap();
+        assert_eq!(
+            bls_aggregate_verify_with_ciphersuite(&pubkeys, &refs(&msgs), &aggregated, BlsCiphersuite::MinSigPop),
+            Ok(())
+        );
+    }
+}
@@ -15,20 +15,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::{string::{String, ToString}, vec::Vec};
+use sp_core::bounded::alloc::vec::Vec;
 use super::Hash256;
-use crate::sha3_256;
+use crate::{sha3_256, VerifyError};
 
 /// Verify ecdsa signature(sha2_256)
-pub fn ecdsa_verify(pubkey: &[u8], msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Result<(), String> {
+pub fn ecdsa_verify(pubkey: &[u8], msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Result<(), VerifyError> {
     inner_ecdsa_verify(pubkey, msg, sig, hash256, |v| v)
 }
 
-/// Recover ecdsa public key(sha2_256)
+/// Recover ecdsa public key(sha2_256), compressed, empty on failure.
+///
+/// Prefer [`ecdsa_recover_pubkey`] which reports why recovery failed.
 pub fn ecdsa_recover(msg: &[u8], sig: &[u8], hash256: Option<Hash256>) -> Vec<u8> {
     inner_ecdsa_recover(msg, sig, hash256, |v| v)
 }
 
+/// Recover ecdsa public key(default: sha2_256) from a 65-byte `r || s || v` signature.
+pub fn ecdsa_recover_pubkey(
+    msg: &[u8],
+    sig: &[u8],
+    hash256: Option<Hash256>,
+    format: PubkeyFormat,
+) -> Result<Vec<u8>, VerifyError> {
+    inner_ecdsa_recover_pubkey(msg, sig, hash256, |v| v, format)
+}
+
 /// Verify ecdsa signature(default: sha2_256)
 pub fn inner_ecdsa_verify<F>(
     pubkey: &[u8],
@@ -36,7 +48,22 @@ pub fn inner_ecdsa_verify<F>(
     sig: &[u8],
     hash256: Option<Hash256>,
     expand: F,
-) -> Result<(), String>
+) -> Result<(), VerifyError>
+    where
+        F: Fn(Vec<u8>) -> Vec<u8>,
+{
+    inner_ecdsa_verify_with_policy(pubkey, msg, sig, hash256, expand, LowSPolicy::Allow)
+}
+
+/// Verify ecdsa signature(default: sha2_256), treating high-S signatures per `policy`.
+pub fn inner_ecdsa_verify_with_policy<F>(
+    pubkey: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+    hash256: Option<Hash256>,
+    expand: F,
+    policy: LowSPolicy,
+) -> Result<(), VerifyError>
     where
         F: Fn(Vec<u8>) -> Vec<u8>,
 {
@@ -50,23 +77,200 @@ pub fn inner_ecdsa_verify<F>(
     };
 
     let hash = expand(hash);
+    if hash.len() != 32 {
+        return Err(VerifyError::InvalidMessage);
+    }
 
     let mut msg = [0u8; 32];
     msg.copy_from_slice(&hash);
     let message = secp256k1::Message::parse(&msg);
-    let signature = secp256k1::Signature::parse_slice(sig).map_err(|e| e.to_string())?;
-    let pubkey = secp256k1::PublicKey::parse_slice(pubkey, None).map_err(|e| e.to_string())?;
+    let signature = parse_ecdsa_signature(sig, policy)?;
+    let pubkey = parse_ecdsa_pubkey(pubkey)?;
     if !secp256k1::verify(&message, &signature, &pubkey) {
-        return Err("ecdsa secp256k1 verify signature failed".to_string());
+        return Err(VerifyError::BadSignature);
     }
     Ok(())
 }
 
-/// Recover ecdsa pubkey(default: sha2_256)
-fn inner_ecdsa_recover<F>(msg: &[u8], sig: &[u8], hash256: Option<Hash256>, expand: F) -> Vec<u8>
+/// Parse a 64-byte compact `r || s` secp256k1 signature, treating high-S per `policy`.
+pub(crate) fn parse_ecdsa_signature(sig: &[u8], policy: LowSPolicy) -> Result<secp256k1::Signature, VerifyError> {
+    if sig.len() != 64 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 64, actual: sig.len() as u32 });
+    }
+    // `Signature::parse_slice` reduces r and s mod n, the chains reject r or s >= n
+    for scalar in sig.chunks(32) {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(scalar);
+        if bool::from(secp256k1::curve::Scalar::default().set_b32(&bytes)) {
+            return Err(VerifyError::InvalidSignature);
+        }
+    }
+    let mut signature = secp256k1::Signature::parse_slice(sig).map_err(|_| VerifyError::InvalidSignature)?;
+    if signature.s.is_high() {
+        match policy {
+            LowSPolicy::Strict => return Err(VerifyError::HighS),
+            LowSPolicy::Normalize => signature.normalize_s(),
+            LowSPolicy::Allow => {}
+        }
+    }
+    Ok(signature)
+}
+
+/// Treatment of high-S secp256k1 signatures.
+///
+/// `(r, s)` and `(r, n - s)` both verify, chains that reject the high-S form
+/// (Bitcoin BIP146, Ethereum EIP-2) must be verified with `Strict` so a malleated
+/// signature is never relayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LowSPolicy {
+    /// Reject high-S signatures.
+    Strict,
+    /// Accept high-S signatures as their low-S counterpart.
+    Normalize,
+    /// Accept either form as is.
+    Allow,
+}
+
+/// Flip a high-S signature to its low-S counterpart, low-S signatures are returned as is.
+///
+/// `sig` is `r || s` or `r || s || v`, negating `s` flips the recovery id carried by `v`
+/// in any of the forms accepted by [`ecdsa_recovery_id`].
+pub fn ecdsa_normalize_signature(sig: &[u8]) -> Result<Vec<u8>, VerifyError> {
+    if sig.len() != 64 && sig.len() != 65 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: sig.len() as u32 });
+    }
+    let mut signature = parse_ecdsa_signature(&sig[..64], LowSPolicy::Allow)?;
+    let high_s = signature.s.is_high();
+    signature.normalize_s();
+    let mut normalized = signature.serialize().to_vec();
+    if let Some(&v) = sig.get(64) {
+        ecdsa_recovery_id(v)?;
+        normalized.push(match v {
+            _ if !high_s => v,
+            0 | 1 => v ^ 1,
+            // 27 and 35 + 2 * chain_id are odd, so recovery id 0 is an odd `v`
+            _ if v % 2 == 1 => v.checked_add(1).ok_or(VerifyError::InvalidRecoveryId)?,
+            _ => v - 1,
+        });
+    }
+    Ok(normalized)
+}
+
+/// Apply `policy` to a `r || s` or `r || s || v` signature, returning the form to relay.
+pub fn ecdsa_apply_low_s_policy(sig: &[u8], policy: LowSPolicy) -> Result<Vec<u8>, VerifyError> {
+    if policy == LowSPolicy::Normalize {
+        return ecdsa_normalize_signature(sig);
+    }
+    if sig.len() != 64 && sig.len() != 65 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: sig.len() as u32 });
+    }
+    parse_ecdsa_signature(&sig[..64], policy)?;
+    if let Some(&v) = sig.get(64) {
+        ecdsa_recovery_id(v)?;
+    }
+    Ok(sig.to_vec())
+}
+
+/// Verify secp256r1 (NIST P-256) ecdsa signature(sha2_256) with a SEC1 encoded public key.
+pub fn secp256r1_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    secp256r1_verify_prehash(pubkey, &sp_io::hashing::sha2_256(msg), sig, LowSPolicy::Allow)
+}
+
+/// Verify a 64-byte `r || s` secp256r1 signature over a message hash, treating high-S
+/// signatures per `policy`.
+pub fn secp256r1_verify_prehash(
+    pubkey: &[u8],
+    hash: &[u8; 32],
+    sig: &[u8],
+    policy: LowSPolicy,
+) -> Result<(), VerifyError> {
+    use p256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+
+    if !matches!(pubkey.len(), 33 | 65) {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: 33, actual: pubkey.len() as u32 });
+    }
+    if sig.len() != 64 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 64, actual: sig.len() as u32 });
+    }
+    let pubkey = VerifyingKey::from_sec1_bytes(pubkey).map_err(|_| VerifyError::InvalidPublicKey)?;
+    let mut signature = Signature::from_slice(sig).map_err(|_| VerifyError::InvalidSignature)?;
+    if let Some(normalized) = signature.normalize_s() {
+        match policy {
+            LowSPolicy::Strict => return Err(VerifyError::HighS),
+            LowSPolicy::Normalize => signature = normalized,
+            LowSPolicy::Allow => {}
+        }
+    }
+    pubkey.verify_prehash(hash, &signature).map_err(|_| VerifyError::BadSignature)
+}
+
+/// Parse a compressed (33), uncompressed (65) or raw (64) secp256k1 public key.
+pub(crate) fn parse_ecdsa_pubkey(pubkey: &[u8]) -> Result<secp256k1::PublicKey, VerifyError> {
+    if !matches!(pubkey.len(), 33 | 64 | 65) {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: 33, actual: pubkey.len() as u32 });
+    }
+    secp256k1::PublicKey::parse_slice(pubkey, None).map_err(|_| VerifyError::InvalidPublicKey)
+}
+
+/// Recover ecdsa pubkey(default: sha2_256), compressed, empty on failure.
+pub fn inner_ecdsa_recover<F>(msg: &[u8], sig: &[u8], hash256: Option<Hash256>, expand: F) -> Vec<u8>
     where
         F: Fn(Vec<u8>) -> Vec<u8>,
 {
+    inner_ecdsa_recover_pubkey(msg, sig, hash256, expand, PubkeyFormat::Compressed).unwrap_or_default()
+}
+
+/// Serialization of a recovered secp256k1 public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PubkeyFormat {
+    /// 33 bytes, `0x02`/`0x03` prefixed
+    Compressed,
+    /// 65 bytes, `0x04` prefixed
+    Uncompressed,
+}
+
+/// Recovery id of a signature `v`: raw (0, 1), Ethereum legacy (27, 28) or
+/// EIP-155 (`chain_id * 2 + 35 + recovery_id`).
+pub fn ecdsa_recovery_id(v: u8) -> Result<u8, VerifyError> {
+    match v {
+        0 | 1 => Ok(v),
+        27 | 28 => Ok(v - 27),
+        v if v >= 35 => Ok((v - 35) % 2),
+        _ => Err(VerifyError::InvalidRecoveryId),
+    }
+}
+
+/// Recover ecdsa pubkey(default: sha2_256) from a 65-byte `r || s || v` signature,
+/// `v` as accepted by [`ecdsa_recovery_id`].
+pub fn inner_ecdsa_recover_pubkey<F>(
+    msg: &[u8],
+    sig: &[u8],
+    hash256: Option<Hash256>,
+    expand: F,
+    format: PubkeyFormat,
+) -> Result<Vec<u8>, VerifyError>
+    where
+        F: Fn(Vec<u8>) -> Vec<u8>,
+{
+    inner_ecdsa_recover_pubkey_with_policy(msg, sig, hash256, expand, format, LowSPolicy::Allow)
+}
+
+/// [`inner_ecdsa_recover_pubkey`], treating high-S signatures per `policy`. A normalized
+/// signature recovers with the flipped recovery id, so the key is that of the signer.
+pub fn inner_ecdsa_recover_pubkey_with_policy<F>(
+    msg: &[u8],
+    sig: &[u8],
+    hash256: Option<Hash256>,
+    expand: F,
+    format: PubkeyFormat,
+    policy: LowSPolicy,
+) -> Result<Vec<u8>, VerifyError>
+    where
+        F: Fn(Vec<u8>) -> Vec<u8>,
+{
+    if sig.len() != 65 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: sig.len() as u32 });
+    }
     let hash256 = hash256.unwrap_or(Hash256::Sha2_256);
     let hash = match hash256 {
         Hash256::Sha2_256 => sp_io::hashing::sha2_256(msg).to_vec(),
@@ -77,20 +281,206 @@ fn inner_ecdsa_recover<F>(msg: &[u8], sig: &[u8], hash256: Option<Hash256>, expa
     };
 
     let hash = expand(hash);
+    if hash.len() != 32 {
+        return Err(VerifyError::InvalidMessage);
+    }
 
     let mut msg = [0u8; 32];
     msg.copy_from_slice(&hash);
     let message = secp256k1::Message::parse(&msg);
-    let signature = match secp256k1::Signature::parse_slice(&sig[..64]) {
-        Ok(sig) => sig,
-        Err(_) => return Vec::new(),
-    };
-    let recovery_id = match secp256k1::RecoveryId::parse(sig[64]) {
-        Ok(recovery_id) => recovery_id,
-        Err(_) => return Vec::new(),
-    };
-    match secp256k1::recover(&message, &signature, &recovery_id) {
-        Ok(pk) => pk.serialize_compressed().to_vec(),
-        Err(_) => Vec::new(),
+    let mut signature = parse_ecdsa_signature(&sig[..64], LowSPolicy::Allow)?;
+    let mut recovery_id = ecdsa_recovery_id(sig[64])?;
+    if signature.s.is_high() {
+        match policy {
+            LowSPolicy::Strict => return Err(VerifyError::HighS),
+            LowSPolicy::Normalize => {
+                signature.normalize_s();
+                recovery_id ^= 1;
+            }
+            LowSPolicy::Allow => {}
+        }
+    }
+    let recovery_id = secp256k1::RecoveryId::parse(recovery_id).map_err(|_| VerifyError::InvalidRecoveryId)?;
+    let pk = secp256k1::recover(&message, &signature, &recovery_id).map_err(|_| VerifyError::InvalidSignature)?;
+    Ok(match format {
+        PubkeyFormat::Compressed => pk.serialize_compressed().to_vec(),
+        PubkeyFormat::Uncompressed => pk.serialize().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(msg: &[u8], seckey: &[u8; 32]) -> (Vec<u8>, secp256k1::PublicKey) {
+        let sk = secp256k1::SecretKey::parse(seckey).unwrap();
+        let hash = sp_io::hashing::sha2_256(msg);
+        let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&hash), &sk);
+        let mut sig = sig.serialize().to_vec();
+        sig.push(recovery_id.serialize());
+        (sig, secp256k1::PublicKey::from_secret_key(&sk))
+    }
+
+    /// xorshift64*, deterministic so failures reproduce
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    #[test]
+    fn recover_accepts_all_v_forms() {
+        let (sig, pk) = sign(b"recover", &[7u8; 32]);
+        let id = sig[64];
+        // raw, legacy, EIP-155 for chain ids 1 and 56
+        for v in [id, id + 27, 37 + id, 147 + id] {
+            let mut sig = sig.clone();
+            sig[64] = v;
+            assert_eq!(
+                ecdsa_recover_pubkey(b"recover", &sig, None, PubkeyFormat::Compressed),
+                Ok(pk.serialize_compressed().to_vec()),
+            );
+            assert_eq!(
+                ecdsa_recover_pubkey(b"recover", &sig, None, PubkeyFormat::Uncompressed),
+                Ok(pk.serialize().to_vec()),
+            );
+        }
+        assert_eq!(ecdsa_recover(b"recover", &sig, None), pk.serialize_compressed().to_vec());
+    }
+
+    #[test]
+    fn recover_rejects_malformed_signatures() {
+        let (sig, _) = sign(b"recover", &[7u8; 32]);
+        for len in [0, 1, 64, 66] {
+            let sig: Vec<u8> = sig.iter().copied().cycle().take(len).collect();
+            assert_eq!(
+                ecdsa_recover_pubkey(b"recover", &sig, None, PubkeyFormat::Compressed),
+                Err(VerifyError::InvalidSignatureLength { expected: 65, actual: len as u32 }),
+            );
+            assert!(ecdsa_recover(b"recover", &sig, None).is_empty());
+        }
+        for v in [2, 3, 26, 29, 34] {
+            let mut sig = sig.clone();
+            sig[64] = v;
+            assert_eq!(
+                ecdsa_recover_pubkey(b"recover", &sig, None, PubkeyFormat::Compressed),
+                Err(VerifyError::InvalidRecoveryId),
+            );
+        }
+        let mut zero = sig.clone();
+        zero[..64].fill(0);
+        assert_eq!(
+            ecdsa_recover_pubkey(b"recover", &zero, None, PubkeyFormat::Compressed),
+            Err(VerifyError::InvalidSignature),
+        );
+    }
+
+    #[test]
+    fn recover_never_panics_on_arbitrary_input() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let (valid, _) = sign(b"fuzz", &[9u8; 32]);
+        for round in 0..2000 {
+            let sig = if round % 2 == 0 {
+                let len = (rng.next() % 80) as usize;
+                rng.bytes(len)
+            } else {
+                // flip a few bytes of a valid signature
+                let mut sig = valid.clone();
+                for _ in 0..=(rng.next() % 3) {
+                    let i = (rng.next() % 65) as usize;
+                    sig[i] = rng.next() as u8;
+                }
+                sig
+            };
+            let len = (rng.next() % 64) as usize;
+            let msg = rng.bytes(len);
+            for format in [PubkeyFormat::Compressed, PubkeyFormat::Uncompressed] {
+                if let Ok(pk) = ecdsa_recover_pubkey(&msg, &sig, None, format) {
+                    let len = if format == PubkeyFormat::Compressed { 33 } else { 65 };
+                    assert_eq!(pk.len(), len);
+                }
+            }
+            let _ = ecdsa_recover(&msg, &sig, Some(Hash256::Keccak256));
+            let expand_len = (rng.next() % 40) as usize;
+            let _ = inner_ecdsa_recover(&msg, &sig, None, |mut v| {
+                v.truncate(expand_len);
+                v
+            });
+        }
+    }
+
+    #[test]
+    fn normalize_flips_high_s_and_recovery_id() {
+        let (low, _) = sign(b"normalize", &[7u8; 32]);
+        let mut signature = secp256k1::Signature::parse_slice(&low[..64]).unwrap();
+        signature.s = -signature.s;
+        let mut high = signature.serialize().to_vec();
+        high.push(0);
+        let id = low[64];
+        // raw, legacy and EIP-155 for chain id 1
+        for (low_v, high_v) in [(id, id ^ 1), (27 + id, 28 - id), (37 + id, 38 - id)] {
+            let mut low = low.clone();
+            low[64] = low_v;
+            high[64] = high_v;
+            assert_eq!(ecdsa_normalize_signature(&high), Ok(low.clone()));
+            assert_eq!(ecdsa_normalize_signature(&low), Ok(low));
+        }
+        assert_eq!(ecdsa_normalize_signature(&high[..64]), Ok(low[..64].to_vec()));
+        // EIP-155 v = 255 flips to a recovery id whose `v` does not fit a byte
+        high[64] = 255;
+        assert_eq!(ecdsa_normalize_signature(&high), Err(VerifyError::InvalidRecoveryId));
+        high[64] = 254;
+        assert_eq!(ecdsa_normalize_signature(&high).map(|sig| sig[64]), Ok(253));
+    }
+
+    #[test]
+    fn rejects_overflowing_scalars() {
+        // r = 1, s = 1 and the same scalars encoded as 1 + n
+        let one = format!("{:0>64}", "1");
+        let one_plus_n = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364142";
+        let valid = hex::decode(format!("{one}{one}")).unwrap();
+        for policy in [LowSPolicy::Strict, LowSPolicy::Normalize, LowSPolicy::Allow] {
+            assert!(parse_ecdsa_signature(&valid, policy).is_ok());
+            assert_eq!(ecdsa_apply_low_s_policy(&valid, policy), Ok(valid.clone()));
+            for sig in [format!("{one}{one_plus_n}"), format!("{one_plus_n}{one}")] {
+                let sig = hex::decode(sig).unwrap();
+                assert_eq!(parse_ecdsa_signature(&sig, policy).err(), Some(VerifyError::InvalidSignature));
+                assert_eq!(ecdsa_apply_low_s_policy(&sig, policy), Err(VerifyError::InvalidSignature));
+            }
+        }
+    }
+
+    #[test]
+    fn secp256r1_verify_applies_low_s_policy() {
+        // key 0x0707..07, message and signatures from an independent implementation
+        let pk = hex::decode("031e18532fd4754c02f3041d9c75ceb33b83ffd81ac7ce4fe882ccb1c98bc5896e").unwrap();
+        let msg = hex::decode("490e078e4dc28d6fc9f572a4a079b010f52ce005c46f08bbe92fbe8fe2adb662").unwrap();
+        let low = hex::decode("1705adcb7ab57da6c99340872dbf9eaa27ec48055d4e6d81635d6a83584ff1784f03f222962261bf973883997e379ee1862ca6250cd82ecc506030f01936b8c1").unwrap();
+        let high = hex::decode("1705adcb7ab57da6c99340872dbf9eaa27ec48055d4e6d81635d6a83584ff178b0fc0ddc69dd9e4168c77c6681c8611e36ba54889a3f6fb8a35999d2e32c6c90").unwrap();
+        let hash = sp_io::hashing::sha2_256(&msg);
+
+        assert_eq!(secp256r1_verify(&pk, &msg, &low), Ok(()));
+        assert_eq!(secp256r1_verify(&pk, &msg, &high), Ok(()));
+        assert_eq!(secp256r1_verify_prehash(&pk, &hash, &high, LowSPolicy::Strict), Err(VerifyError::HighS));
+        assert_eq!(secp256r1_verify_prehash(&pk, &hash, &high, LowSPolicy::Normalize), Ok(()));
+        assert_eq!(secp256r1_verify_prehash(&pk, &hash, &low, LowSPolicy::Strict), Ok(()));
+        assert_eq!(secp256r1_verify(&pk, b"other", &low), Err(VerifyError::BadSignature));
+        assert_eq!(
+            secp256r1_verify(&pk[1..], &msg, &low),
+            Err(VerifyError::InvalidPublicKeyLength { expected: 33, actual: 32 }),
+        );
+        let mut off_curve = [0xffu8; 33];
+        off_curve[0] = 2;
+        assert_eq!(secp256r1_verify(&off_curve, &msg, &low), Err(VerifyError::InvalidPublicKey));
+        assert_eq!(secp256r1_verify(&pk, &msg, &[0u8; 64]), Err(VerifyError::InvalidSignature));
     }
 }
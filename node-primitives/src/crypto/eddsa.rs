@@ -15,16 +15,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::string::{String, ToString};
+use crate::VerifyError;
 
 /// Verify ed25519 signature
-pub fn ed25519_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
+pub fn ed25519_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
     use sp_core::ed25519::{Public, Signature};
 
-    let pk = Public::try_from(pubkey).map_err(|_| "ed25519 pubkey from bytes failed".to_string())?;
-    let signature = Signature::try_from(sig).map_err(|_| "ed25519 signature from bytes failed".to_string())?;
+    let pk = Public::try_from(pubkey).map_err(|_| VerifyError::InvalidPublicKeyLength {
+        expected: 32,
+        actual: pubkey.len() as u32,
+    })?;
+    let signature = Signature::try_from(sig).map_err(|_| VerifyError::InvalidSignatureLength {
+        expected: 64,
+        actual: sig.len() as u32,
+    })?;
     if !sp_io::crypto::ed25519_verify(&signature, msg, &pk) {
-        return Err("verify ed25519 signature failed".to_string());
+        return Err(VerifyError::BadSignature);
     }
     Ok(())
 }
@@ -0,0 +1,939 @@
+// This file is part of SafeXNetwork.
+
+// Copyright (C) SafeXNetwork (HK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// 	http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Coordinator side of FROST threshold Schnorr signing (RFC 9591).
+//!
+//! Each signer publishes a commitment `(identifier, hiding, binding)` in round one and a
+//! signature share in round two. The coordinator checks every share against the signer's
+//! verifying share before aggregating, so a misbehaving signer is pinpointed instead of
+//! producing an invalid group signature.
+
+use crate::VerifyError;
+use codec::{Decode, Encode};
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar as EdScalar,
+    traits::IsIdentity,
+};
+use secp256k1::curve::{Affine, Jacobian, Scalar as SecpScalar, ECMULT_CONTEXT};
+use secp256k1::{PublicKey as ECPK, PublicKeyFormat};
+use sha2::{Digest, Sha256, Sha512};
+use sp_core::bounded::alloc::vec::Vec;
+
+/// FROST ciphersuites of RFC 9591.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum FrostCiphersuite {
+    /// FROST(Ed25519, SHA-512), signatures verify as plain ed25519 signatures.
+    Ed25519Sha512,
+    /// FROST(secp256k1, SHA-256), 33-byte compressed points and 32-byte big endian scalars.
+    Secp256k1Sha256,
+}
+
+/// Round one commitment of the signer `identifier`, as serialized group elements.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct FrostCommitment {
+    pub identifier: u16,
+    pub hiding: Vec<u8>,
+    pub binding: Vec<u8>,
+}
+
+/// Round two signature share of the signer `identifier`, as a serialized scalar.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct FrostSignatureShare {
+    pub identifier: u16,
+    pub share: Vec<u8>,
+}
+
+/// Prime order group and hash functions of a ciphersuite.
+trait FrostGroup {
+    type Scalar: Clone;
+    type Element: Clone;
+
+    const ELEMENT_LEN: usize;
+    const SCALAR_LEN: usize;
+
+    /// Fails on bytes that are not a canonical encoding of a non-identity element.
+    fn deserialize_element(buf: &[u8]) -> Option<Self::Element>;
+    /// Fails on the identity, which has no encoding.
+    fn serialize_element(element: &Self::Element) -> Option<Vec<u8>>;
+    /// Fails on scalars outside `[0, order)`.
+    fn deserialize_scalar(buf: &[u8]) -> Option<Self::Scalar>;
+    fn serialize_scalar(scalar: &Self::Scalar) -> Vec<u8>;
+    fn scalar_from_u16(value: u16) -> Self::Scalar;
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_neg(a: &Self::Scalar) -> Self::Scalar;
+    fn scalar_inv(a: &Self::Scalar) -> Self::Scalar;
+    /// `sum(s_i * P_i) + g * G`, `terms` is never empty.
+    fn lincomb(terms: &[(Self::Scalar, Self::Element)], g: &Self::Scalar) -> Self::Element;
+    fn is_identity(element: &Self::Element) -> bool;
+    /// Binding factor hash.
+    fn h1(m: &[u8]) -> Self::Scalar;
+    /// Challenge hash.
+    fn h2(m: &[u8]) -> Self::Scalar;
+    /// Message hash.
+    fn h4(m: &[u8]) -> Vec<u8>;
+    /// Commitment list hash.
+    fn h5(m: &[u8]) -> Vec<u8>;
+}
+
+struct FrostEd25519;
+
+impl FrostEd25519 {
+    const CONTEXT: &'static [u8] = b"FROST-ED25519-SHA512-v1";
+
+    fn hash(tag: &[u8], m: &[u8]) -> [u8; 64] {
+        let mut hasher = Sha512::new();
+        hasher.update(Self::CONTEXT);
+        hasher.update(tag);
+        hasher.update(m);
+        let mut out = [0u8; 64];
+        out.copy_from_slice(&hasher.finalize()[..]);
+        out
+    }
+}
+
+impl FrostGroup for FrostEd25519 {
+    type Scalar = EdScalar;
+    type Element = EdwardsPoint;
+
+    const ELEMENT_LEN: usize = 32;
+    const SCALAR_LEN: usize = 32;
+
+    fn deserialize_element(buf: &[u8]) -> Option<EdwardsPoint> {
+        let compressed = CompressedEdwardsY::from_slice(buf).ok()?;
+        let point = compressed.decompress()?;
+        // reject non-canonical y, the identity and points with a torsion component
+        if point.compress() != compressed || point.is_identity() || !point.is_torsion_free() {
+            return None;
+        }
+        Some(point)
+    }
+
+    fn serialize_element(element: &EdwardsPoint) -> Option<Vec<u8>> {
+        (!element.is_identity()).then(|| element.compress().to_bytes().to_vec())
+    }
+
+    fn deserialize_scalar(buf: &[u8]) -> Option<EdScalar> {
+        Option::from(EdScalar::from_canonical_bytes(buf.try_into().ok()?))
+    }
+
+    fn serialize_scalar(scalar: &EdScalar) -> Vec<u8> {
+        scalar.to_bytes().to_vec()
+    }
+
+    fn scalar_from_u16(value: u16) -> EdScalar {
+        EdScalar::from(value)
+    }
+
+    fn scalar_add(a: &EdScalar, b: &EdScalar) -> EdScalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &EdScalar, b: &EdScalar) -> EdScalar {
+        a * b
+    }
+
+    fn scalar_neg(a: &EdScalar) -> EdScalar {
+        -a
+    }
+
+    fn scalar_inv(a: &EdScalar) -> EdScalar {
+        a.invert()
+    }
+
+    fn lincomb(terms: &[(EdScalar, EdwardsPoint)], g: &EdScalar) -> EdwardsPoint {
+        terms.iter().fold(EdwardsPoint::mul_base(g), |acc, (s, p)| acc + s * p)
+    }
+
+    fn is_identity(element: &EdwardsPoint) -> bool {
+        element.is_identity()
+    }
+
+    fn h1(m: &[u8]) -> EdScalar {
+        EdScalar::from_bytes_mod_order_wide(&Self::hash(b"rho", m))
+    }
+
+    // plain SHA-512 so that group signatures are valid ed25519 signatures
+    fn h2(m: &[u8]) -> EdScalar {
+        let mut out = [0u8; 64];
+        out.copy_from_slice(&Sha512::digest(m)[..]);
+        EdScalar::from_bytes_mod_order_wide(&out)
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        Self::hash(b"msg", m).to_vec()
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        Self::hash(b"com", m).to_vec()
+    }
+}
+
+struct FrostSecp256k1;
+
+impl FrostSecp256k1 {
+    const CONTEXT: &'static [u8] = b"FROST-secp256k1-SHA256-v1";
+
+    fn hash(tag: &[u8], m: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(Self::CONTEXT);
+        hasher.update(tag);
+        hasher.update(m);
+        hasher.finalize().to_vec()
+    }
+
+    // hash_to_field of RFC 9380 with expand_message_xmd(SHA-256), L = 48 and DST = CONTEXT || tag
+    fn hash_to_scalar(tag: &[u8], m: &[u8]) -> SecpScalar {
+        let dst_len = [(Self::CONTEXT.len() + tag.len()) as u8];
+        let b0 = Sha256::new()
+            .chain([0u8; 64])
+            .chain(m)
+            .chain([0u8, 48, 0])
+            .chain(Self::CONTEXT)
+            .chain(tag)
+            .chain(dst_len)
+            .finalize();
+        let b1 = Sha256::new().chain(b0).chain([1u8]).chain(Self::CONTEXT).chain(tag).chain(dst_len).finalize();
+        let mut b0_xor_b1 = [0u8; 32];
+        b0_xor_b1.iter_mut().zip(b0.iter().zip(b1.iter())).for_each(|(x, (a, b))| *x = a ^ b);
+        let b2 = Sha256::new().chain(b0_xor_b1).chain([2u8]).chain(Self::CONTEXT).chain(tag).chain(dst_len).finalize();
+
+        // uniform = b1 || b2[..16], reduce hi * 2^256 + lo mod n
+        let mut hi = [0u8; 32];
+        hi[16..].copy_from_slice(&b1[..16]);
+        let mut lo = [0u8; 32];
+        lo[..16].copy_from_slice(&b1[16..]);
+        lo[16..].copy_from_slice(&b2[..16]);
+        // 2^256 mod n
+        let mut shift = [0u8; 32];
+        shift[15..].copy_from_slice(&[
+            0x01, 0x45, 0x51, 0x23, 0x19, 0x50, 0xb7, 0x5f, 0xc4, 0x40, 0x2d, 0xa1, 0x73, 0x2f, 0xc9, 0xbe, 0xbf,
+        ]);
+        let (mut h, mut l, mut s) = (SecpScalar::default(), SecpScalar::default(), SecpScalar::default());
+        let _ = h.set_b32(&hi);
+        let _ = l.set_b32(&lo);
+        let _ = s.set_b32(&shift);
+        h * s + l
+    }
+}
+
+impl FrostGroup for FrostSecp256k1 {
+    type Scalar = SecpScalar;
+    type Element = Jacobian;
+
+    const ELEMENT_LEN: usize = 33;
+    const SCALAR_LEN: usize = 32;
+
+    fn deserialize_element(buf: &[u8]) -> Option<Jacobian> {
+        // SEC1 decoding never yields the identity
+        let pk = ECPK::parse_slice(buf, Some(PublicKeyFormat::Compressed)).ok()?;
+        let affine: Affine = pk.into();
+        Some(Jacobian::from_ge(&affine))
+    }
+
+    fn serialize_element(element: &Jacobian) -> Option<Vec<u8>> {
+        if element.is_infinity() {
+            return None;
+        }
+        let mut affine = Affine::from_gej(element);
+        affine.x.normalize_var();
+        affine.y.normalize_var();
+        let mut out = Vec::with_capacity(33);
+        out.push(if affine.y.is_odd() { 0x03 } else { 0x02 });
+        out.extend_from_slice(&affine.x.b32());
+        Some(out)
+    }
+
+    fn deserialize_scalar(buf: &[u8]) -> Option<SecpScalar> {
+        let mut scalar = SecpScalar::default();
+        let overflow = scalar.set_b32(buf.try_into().ok()?);
+        (!bool::from(overflow)).then_some(scalar)
+    }
+
+    fn serialize_scalar(scalar: &SecpScalar) -> Vec<u8> {
+        scalar.b32().to_vec()
+    }
+
+    fn scalar_from_u16(value: u16) -> SecpScalar {
+        SecpScalar::from_int(value as u32)
+    }
+
+    fn scalar_add(a: &SecpScalar, b: &SecpScalar) -> SecpScalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &SecpScalar, b: &SecpScalar) -> SecpScalar {
+        a * b
+    }
+
+    fn scalar_neg(a: &SecpScalar) -> SecpScalar {
+        -a
+    }
+
+    fn scalar_inv(a: &SecpScalar) -> SecpScalar {
+        a.inv_var()
+    }
+
+    fn lincomb(terms: &[(SecpScalar, Jacobian)], g: &SecpScalar) -> Jacobian {
+        let zero = SecpScalar::from_int(0);
+        let mut acc = Jacobian::default();
+        acc.set_infinity();
+        for (i, (s, p)) in terms.iter().enumerate() {
+            let mut term = Jacobian::default();
+            // g * G is folded into the first multiplication
+            ECMULT_CONTEXT.ecmult(&mut term, p, s, if i == 0 { g } else { &zero });
+            acc = acc.add_var(&term, None);
+        }
+        acc
+    }
+
+    fn is_identity(element: &Jacobian) -> bool {
+        element.is_infinity()
+    }
+
+    fn h1(m: &[u8]) -> SecpScalar {
+        Self::hash_to_scalar(b"rho", m)
+    }
+
+    fn h2(m: &[u8]) -> SecpScalar {
+        Self::hash_to_scalar(b"chal", m)
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        Self::hash(b"msg", m)
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        Self::hash(b"com", m)
+    }
+}
+
+fn parse_pubkey<G: FrostGroup>(buf: &[u8]) -> Result<G::Element, VerifyError> {
+    if buf.len() != G::ELEMENT_LEN {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: G::ELEMENT_LEN as u32, actual: buf.len() as u32 });
+    }
+    G::deserialize_element(buf).ok_or(VerifyError::InvalidPublicKey)
+}
+
+fn parse_scalar<G: FrostGroup>(buf: &[u8]) -> Result<G::Scalar, VerifyError> {
+    if buf.len() != G::SCALAR_LEN {
+        return Err(VerifyError::InvalidSignatureLength { expected: G::SCALAR_LEN as u32, actual: buf.len() as u32 });
+    }
+    G::deserialize_scalar(buf).ok_or(VerifyError::InvalidSignature)
+}
+
+/// Group key, parsed commitments and everything derived from them for one signing session.
+struct SigningPackage<G: FrostGroup> {
+    identifiers: Vec<u16>,
+    commitments: Vec<(G::Element, G::Element)>,
+    binding_factors: Vec<G::Scalar>,
+    group_commitment: G::Element,
+    challenge: G::Scalar,
+}
+
+impl<G: FrostGroup> SigningPackage<G> {
+    fn new(group_pubkey: &[u8], commitments: &[FrostCommitment], msg: &[u8]) -> Result<Self, VerifyError> {
+        parse_pubkey::<G>(group_pubkey)?;
+        let encoded = encode_commitments::<G>(commitments)?;
+        let parsed = commitments
+            .iter()
+            .map(|c| {
+                Ok((
+                    G::deserialize_element(&c.hiding).ok_or(VerifyError::InvalidCommitment)?,
+                    G::deserialize_element(&c.binding).ok_or(VerifyError::InvalidCommitment)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, VerifyError>>()?;
+
+        let mut rho_input = group_pubkey.to_vec();
+        rho_input.extend(G::h4(msg));
+        rho_input.extend(G::h5(&encoded));
+        let prefix_len = rho_input.len();
+        let binding_factors: Vec<G::Scalar> = commitments
+            .iter()
+            .map(|c| {
+                rho_input.truncate(prefix_len);
+                rho_input.extend(G::serialize_scalar(&G::scalar_from_u16(c.identifier)));
+                G::h1(&rho_input)
+            })
+            .collect();
+
+        let mut terms = Vec::with_capacity(2 * commitments.len());
+        for ((hiding, binding), rho) in parsed.iter().zip(&binding_factors) {
+            terms.push((G::scalar_from_u16(1), hiding.clone()));
+            terms.push((rho.clone(), binding.clone()));
+        }
+        let group_commitment = G::lincomb(&terms, &G::scalar_from_u16(0));
+        let r = G::serialize_element(&group_commitment).ok_or(VerifyError::InvalidCommitment)?;
+        let challenge = G::h2(&[&r[..], group_pubkey, msg].concat());
+
+        Ok(SigningPackage {
+            identifiers: commitments.iter().map(|c| c.identifier).collect(),
+            commitments: parsed,
+            binding_factors,
+            group_commitment,
+            challenge,
+        })
+    }
+
+    // lambda_i = prod_{j != i} x_j / (x_j - x_i)
+    fn lagrange_coefficient(&self, index: usize) -> G::Scalar {
+        let x_i = G::scalar_from_u16(self.identifiers[index]);
+        let (mut num, mut den) = (G::scalar_from_u16(1), G::scalar_from_u16(1));
+        for (j, id) in self.identifiers.iter().enumerate() {
+            if j != index {
+                let x_j = G::scalar_from_u16(*id);
+                num = G::scalar_mul(&num, &x_j);
+                den = G::scalar_mul(&den, &G::scalar_add(&x_j, &G::scalar_neg(&x_i)));
+            }
+        }
+        G::scalar_mul(&num, &G::scalar_inv(&den))
+    }
+
+    // z_i * G == hiding_i + rho_i * binding_i + (c * lambda_i) * PK_i
+    fn verify_share(&self, index: usize, verifying_share: &G::Element, share: &G::Scalar) -> Result<(), VerifyError> {
+        let (hiding, binding) = &self.commitments[index];
+        let c_lambda = G::scalar_mul(&self.challenge, &self.lagrange_coefficient(index));
+        let terms = [
+            (G::scalar_from_u16(1), hiding.clone()),
+            (self.binding_factors[index].clone(), binding.clone()),
+            (c_lambda, verifying_share.clone()),
+        ];
+        if !G::is_identity(&G::lincomb(&terms, &G::scalar_neg(share))) {
+            return Err(VerifyError::BadSignature);
+        }
+        Ok(())
+    }
+
+    fn position(&self, identifier: u16) -> Result<usize, VerifyError> {
+        self.identifiers.binary_search(&identifier).map_err(|_| VerifyError::InvalidCommitment)
+    }
+}
+
+// identifier || hiding || binding of each signer, identifiers strictly ascending
+fn encode_commitments<G: FrostGroup>(commitments: &[FrostCommitment]) -> Result<Vec<u8>, VerifyError> {
+    if commitments.is_empty() {
+        return Err(VerifyError::InvalidCommitment);
+    }
+    let mut out = Vec::with_capacity(commitments.len() * (G::SCALAR_LEN + 2 * G::ELEMENT_LEN));
+    let mut last = 0u16;
+    for (i, c) in commitments.iter().enumerate() {
+        if c.identifier <= last
+            || G::deserialize_element(&c.hiding).is_none()
+            || G::deserialize_element(&c.binding).is_none()
+        {
+            return Err(VerifyError::at_index(i, VerifyError::InvalidCommitment));
+        }
+        last = c.identifier;
+        out.extend(G::serialize_scalar(&G::scalar_from_u16(c.identifier)));
+        out.extend_from_slice(&c.hiding);
+        out.extend_from_slice(&c.binding);
+    }
+    Ok(out)
+}
+
+fn binding_factors<G: FrostGroup>(
+    group_pubkey: &[u8],
+    commitments: &[FrostCommitment],
+    msg: &[u8],
+) -> Result<Vec<Vec<u8>>, VerifyError> {
+    let package = SigningPackage::<G>::new(group_pubkey, commitments, msg)?;
+    Ok(package.binding_factors.iter().map(G::serialize_scalar).collect())
+}
+
+fn verify_signature_share<G: FrostGroup>(
+    group_pubkey: &[u8],
+    verifying_share: &[u8],
+    commitments: &[FrostCommitment],
+    share: &FrostSignatureShare,
+    msg: &[u8],
+) -> Result<(), VerifyError> {
+    let package = SigningPackage::<G>::new(group_pubkey, commitments, msg)?;
+    let index = package.position(share.identifier)?;
+    let verifying_share = parse_pubkey::<G>(verifying_share)?;
+    package.verify_share(index, &verifying_share, &parse_scalar::<G>(&share.share)?)
+}
+
+fn aggregate<G: FrostGroup>(
+    group_pubkey: &[u8],
+    verifying_shares: &[&[u8]],
+    commitments: &[FrostCommitment],
+    shares: &[FrostSignatureShare],
+    msg: &[u8],
+) -> Result<Vec<u8>, VerifyError> {
+    let package = SigningPackage::<G>::new(group_pubkey, commitments, msg)?;
+    if shares.len() != commitments.len() || verifying_shares.len() != commitments.len() {
+        return Err(VerifyError::InvalidSignature);
+    }
+    let mut z = G::scalar_from_u16(0);
+    for (i, (share, verifying_share)) in shares.iter().zip(verifying_shares).enumerate() {
+        let check = || {
+            if share.identifier != package.identifiers[i] {
+                return Err(VerifyError::InvalidCommitment);
+            }
+            let verifying_share = parse_pubkey::<G>(verifying_share)?;
+            let share = parse_scalar::<G>(&share.share)?;
+            package.verify_share(i, &verifying_share, &share)?;
+            Ok(share)
+        };
+        let share = check().map_err(|e| VerifyError::at_index(i, e))?;
+        z = G::scalar_add(&z, &share);
+    }
+
+    let mut signature = G::serialize_element(&package.group_commitment).ok_or(VerifyError::InvalidCommitment)?;
+    signature.extend(G::serialize_scalar(&z));
+    Ok(signature)
+}
+
+// z * G == R + c * PK
+fn verify<G: FrostGroup>(group_pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    let pk = parse_pubkey::<G>(group_pubkey)?;
+    if sig.len() != G::ELEMENT_LEN + G::SCALAR_LEN {
+        return Err(VerifyError::InvalidSignatureLength {
+            expected: (G::ELEMENT_LEN + G::SCALAR_LEN) as u32,
+            actual: sig.len() as u32,
+        });
+    }
+    let (r_enc, z) = sig.split_at(G::ELEMENT_LEN);
+    let r = G::deserialize_element(r_enc).ok_or(VerifyError::InvalidSignature)?;
+    let z = parse_scalar::<G>(z)?;
+    let c = G::h2(&[r_enc, group_pubkey, msg].concat());
+    let terms = [(G::scalar_from_u16(1), r), (c, pk)];
+    if !G::is_identity(&G::lincomb(&terms, &G::scalar_neg(&z))) {
+        return Err(VerifyError::BadSignature);
+    }
+    Ok(())
+}
+
+macro_rules! with_ciphersuite {
+    ($suite:expr, $f:ident($($arg:expr),*)) => {
+        match $suite {
+            FrostCiphersuite::Ed25519Sha512 => $f::<FrostEd25519>($($arg),*),
+            FrostCiphersuite::Secp256k1Sha256 => $f::<FrostSecp256k1>($($arg),*),
+        }
+    };
+}
+
+/// Encode a commitment list as hashed into the binding factors. Commitments must be sorted
+/// by strictly ascending, non-zero identifier.
+pub fn frost_encode_commitments(suite: FrostCiphersuite, commitments: &[FrostCommitment]) -> Result<Vec<u8>, VerifyError> {
+    with_ciphersuite!(suite, encode_commitments(commitments))
+}
+
+/// Serialized binding factor of each signer, in commitment order.
+pub fn frost_binding_factors(
+    suite: FrostCiphersuite,
+    group_pubkey: &[u8],
+    commitments: &[FrostCommitment],
+    msg: &[u8],
+) -> Result<Vec<Vec<u8>>, VerifyError> {
+    with_ciphersuite!(suite, binding_factors(group_pubkey, commitments, msg))
+}
+
+/// Verify the signature share of one signer against its verifying share, i.e. the public key
+/// of its secret share.
+pub fn frost_verify_signature_share(
+    suite: FrostCiphersuite,
+    group_pubkey: &[u8],
+    verifying_share: &[u8],
+    commitments: &[FrostCommitment],
+    share: &FrostSignatureShare,
+    msg: &[u8],
+) -> Result<(), VerifyError> {
+    with_ciphersuite!(suite, verify_signature_share(group_pubkey, verifying_share, commitments, share, msg))
+}
+
+/// Verify every share and aggregate them into the group signature `R || z`.
+///
+/// `shares` and `verifying_shares` are in commitment order. The first share that fails is
+/// reported with its index, so the coordinator can exclude that signer and retry.
+pub fn frost_aggregate(
+    suite: FrostCiphersuite,
+    group_pubkey: &[u8],
+    verifying_shares: &[&[u8]],
+    commitments: &[FrostCommitment],
+    shares: &[FrostSignatureShare],
+    msg: &[u8],
+) -> Result<Vec<u8>, VerifyError> {
+    with_ciphersuite!(suite, aggregate(group_pubkey, verifying_shares, commitments, shares, msg))
+}
+
+/// Verify a FROST group signature `R || z`.
+pub fn frost_verify(suite: FrostCiphersuite, group_pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    with_ciphersuite!(suite, verify(group_pubkey, msg, sig))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ed25519_verify;
+
+    const ED25519_BASEPOINT: &str = "5866666666666666666666666666666666666666666666666666666666666666";
+    const SECP256K1_GENERATOR: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    /// Trusted dealer 2-of-3 key material: group key, then `(identifier, secret share, verifying share)`.
+    struct Keys<G: FrostGroup> {
+        group_pubkey: Vec<u8>,
+        signers: Vec<(u16, G::Scalar, Vec<u8>)>,
+    }
+
+    fn scalar<G: FrostGroup>(tag: &[u8], i: u16) -> G::Scalar {
+        G::h1(&[tag, &i.to_be_bytes()].concat())
+    }
+
+    fn base_mul<G: FrostGroup>(generator: &str, s: &G::Scalar) -> Vec<u8> {
+        let generator = G::deserialize_element(&hex::decode(generator).unwrap()).unwrap();
+        G::serialize_element(&G::lincomb(&[(s.clone(), generator)], &G::scalar_from_u16(0))).unwrap()
+    }
+
+    fn keys<G: FrostGroup>(generator: &str) -> Keys<G> {
+        let secret = scalar::<G>(b"secret", 0);
+        let coefficient = scalar::<G>(b"coefficient", 0);
+        let signers = (1..=3u16)
+            .map(|id| {
+                let share = G::scalar_add(&secret, &G::scalar_mul(&coefficient, &G::scalar_from_u16(id)));
+                let verifying_share = base_mul::<G>(generator, &share);
+                (id, share, verifying_share)
+            })
+            .collect();
+        Keys { group_pubkey: base_mul::<G>(generator, &secret), signers }
+    }
+
+    /// Run both signing rounds for the signers at `participants`.
+    fn sign<G: FrostGroup>(
+        generator: &str,
+        keys: &Keys<G>,
+        participants: &[usize],
+        msg: &[u8],
+    ) -> (Vec<FrostCommitment>, Vec<FrostSignatureShare>) {
+        let nonces: Vec<_> = participants
+            .iter()
+            .map(|&p| (scalar::<G>(b"hiding", p as u16), scalar::<G>(b"binding", p as u16)))
+            .collect();
+        let commitments: Vec<_> = participants
+            .iter()
+            .zip(&nonces)
+            .map(|(&p, (d, e))| FrostCommitment {
+                identifier: keys.signers[p].0,
+                hiding: base_mul::<G>(generator, d),
+                binding: base_mul::<G>(generator, e),
+            })
+            .collect();
+        let package = SigningPackage::<G>::new(&keys.group_pubkey, &commitments, msg).unwrap();
+        let shares = participants
+            .iter()
+            .zip(&nonces)
+            .enumerate()
+            .map(|(i, (&p, (d, e)))| {
+                // z_i = d_i + e_i * rho_i + lambda_i * s_i * c
+                let lambda_s = G::scalar_mul(&package.lagrange_coefficient(i), &keys.signers[p].1);
+                let z = G::scalar_add(
+                    &G::scalar_add(d, &G::scalar_mul(e, &package.binding_factors[i])),
+                    &G::scalar_mul(&lambda_s, &package.challenge),
+                );
+                FrostSignatureShare { identifier: keys.signers[p].0, share: G::serialize_scalar(&z) }
+            })
+            .collect();
+        (commitments, shares)
+    }
+
+    fn verifying_shares<'a, G: FrostGroup>(keys: &'a Keys<G>, participants: &[usize]) -> Vec<&'a [u8]> {
+        participants.iter().map(|&p| &keys.signers[p].2[..]).collect()
+    }
+
+    fn sign_and_aggregate<G: FrostGroup>(suite: FrostCiphersuite, generator: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let keys = keys::<G>(generator);
+        let msg = b"frost threshold signature";
+        let mut signatures = Vec::new();
+        for participants in [&[0, 1][..], &[0, 2], &[1, 2], &[0, 1, 2]] {
+            let (commitments, shares) = sign::<G>(generator, &keys, participants, msg);
+            let verifying_shares = verifying_shares(&keys, participants);
+            for (share, verifying_share) in shares.iter().zip(&verifying_shares) {
+                assert_eq!(
+                    frost_verify_signature_share(suite, &keys.group_pubkey, verifying_share, &commitments, share, msg),
+                    Ok(())
+                );
+            }
+            let signature =
+                frost_aggregate(suite, &keys.group_pubkey, &verifying_shares, &commitments, &shares, msg).unwrap();
+            assert_eq!(frost_verify(suite, &keys.group_pubkey, msg, &signature), Ok(()));
+            assert_eq!(frost_verify(suite, &keys.group_pubkey, b"other", &signature), Err(VerifyError::BadSignature));
+            signatures.push((keys.group_pubkey.clone(), signature));
+        }
+        signatures
+    }
+
+    #[test]
+    fn ed25519_group_signature_is_plain_ed25519() {
+        for (pubkey, signature) in sign_and_aggregate::<FrostEd25519>(FrostCiphersuite::Ed25519Sha512, ED25519_BASEPOINT) {
+            assert_eq!(signature.len(), 64);
+            assert_eq!(ed25519_verify(&pubkey, b"frost threshold signature", &signature), Ok(()));
+        }
+    }
+
+    #[test]
+    fn secp256k1_sign_and_aggregate() {
+        for (_, signature) in sign_and_aggregate::<FrostSecp256k1>(FrostCiphersuite::Secp256k1Sha256, SECP256K1_GENERATOR) {
+            assert_eq!(signature.len(), 65);
+        }
+    }
+
+    fn misbehaving_signer<G: FrostGroup>(suite: FrostCiphersuite, generator: &str) {
+        let keys = keys::<G>(generator);
+        let msg = b"frost threshold signature";
+        let participants = [0, 1, 2];
+        let (commitments, mut shares) = sign::<G>(generator, &keys, &participants, msg);
+        let verifying_shares = verifying_shares(&keys, &participants);
+
+        // a share computed with the wrong binding factor
+        let (_, other) = sign::<G>(generator, &keys, &participants, b"other message");
+        shares[1] = other[1].clone();
+        assert_eq!(
+            frost_verify_signature_share(suite, &keys.group_pubkey, verifying_shares[1], &commitments, &shares[1], msg),
+            Err(VerifyError::BadSignature)
+        );
+        assert_eq!(
+            frost_aggregate(suite, &keys.group_pubkey, &verifying_shares, &commitments, &shares, msg),
+            Err(VerifyError::at_index(1, VerifyError::BadSignature))
+        );
+
+        // a valid share attributed to another signer
+        let (_, mut shares) = sign::<G>(generator, &keys, &participants, msg);
+        shares.swap(0, 2);
+        assert_eq!(
+            frost_aggregate(suite, &keys.group_pubkey, &verifying_shares, &commitments, &shares, msg),
+            Err(VerifyError::at_index(0, VerifyError::InvalidCommitment))
+        );
+        let (id0, id2) = (shares[0].identifier, shares[2].identifier);
+        shares[0].identifier = id2;
+        shares[2].identifier = id0;
+        assert_eq!(
+            frost_aggregate(suite, &keys.group_pubkey, &verifying_shares, &commitments, &shares, msg),
+            Err(VerifyError::at_index(0, VerifyError::BadSignature))
+        );
+
+        let mut shares = sign::<G>(generator, &keys, &participants, msg).1;
+        shares[2].share = vec![0xff; G::SCALAR_LEN];
+        assert_eq!(
+            frost_aggregate(suite, &keys.group_pubkey, &verifying_shares, &commitments, &shares, msg),
+            Err(VerifyError::at_index(2, VerifyError::InvalidSignature))
+        );
+    }
+
+    #[test]
+    fn aggregate_pinpoints_misbehaving_signer() {
+        misbehaving_signer::<FrostEd25519>(FrostCiphersuite::Ed25519Sha512, ED25519_BASEPOINT);
+        misbehaving_signer::<FrostSecp256k1>(FrostCiphersuite::Secp256k1Sha256, SECP256K1_GENERATOR);
+    }
+
+    fn malformed_commitments<G: FrostGroup>(suite: FrostCiphersuite, generator: &str) {
+        let keys = keys::<G>(generator);
+        let (commitments, _) = sign::<G>(generator, &keys, &[0, 1, 2], b"msg");
+        assert!(frost_encode_commitments(suite, &commitments).is_ok());
+        assert_eq!(frost_encode_commitments(suite, &[]), Err(VerifyError::InvalidCommitment));
+
+        let mut unsorted = commitments.clone();
+        unsorted.swap(1, 2);
+        assert_eq!(
+            frost_encode_commitments(suite, &unsorted),
+            Err(VerifyError::at_index(2, VerifyError::InvalidCommitment))
+        );
+        let mut duplicate = commitments.clone();
+        duplicate[1].identifier = duplicate[0].identifier;
+        assert_eq!(
+            frost_encode_commitments(suite, &duplicate),
+            Err(VerifyError::at_index(1, VerifyError::InvalidCommitment))
+        );
+        let mut zero = commitments.clone();
+        zero[0].identifier = 0;
+        assert_eq!(
+            frost_encode_commitments(suite, &zero),
+            Err(VerifyError::at_index(0, VerifyError::InvalidCommitment))
+        );
+        let mut bad_point = commitments.clone();
+        bad_point[2].binding = vec![0xff; G::ELEMENT_LEN];
+        assert_eq!(
+            frost_binding_factors(suite, &keys.group_pubkey, &bad_point, b"msg"),
+            Err(VerifyError::at_index(2, VerifyError::InvalidCommitment))
+        );
+        assert_eq!(
+            frost_binding_factors(suite, &keys.group_pubkey[1..], &commitments, b"msg"),
+            Err(VerifyError::InvalidPublicKeyLength { expected: G::ELEMENT_LEN as u32, actual: G::ELEMENT_LEN as u32 - 1 })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_commitments() {
+        malformed_commitments::<FrostEd25519>(FrostCiphersuite::Ed25519Sha512, ED25519_BASEPOINT);
+        malformed_commitments::<FrostSecp256k1>(FrostCiphersuite::Secp256k1Sha256, SECP256K1_GENERATOR);
+    }
+
+    #[test]
+    fn ed25519_rejects_small_order_and_non_canonical_points() {
+        // identity, a point of order 8 and the non-canonical encoding of y = 1
+        for point in [
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ] {
+            assert!(FrostEd25519::deserialize_element(&hex::decode(point).unwrap()).is_none(), "{point}");
+        }
+    }
+
+    fn commitment(identifier: u16, hiding: &str, binding: &str) -> FrostCommitment {
+        FrostCommitment { identifier, hiding: hex::decode(hiding).unwrap(), binding: hex::decode(binding).unwrap() }
+    }
+
+    /// One 2-of-3 signing run of RFC 9591 Appendix E, with signers 1 and 3 and message "test".
+    struct RfcVector {
+        group_pubkey: &'static str,
+        signers: [RfcSigner; 2],
+        sig: &'static str,
+    }
+
+    struct RfcSigner {
+        identifier: u16,
+        participant_share: &'static str,
+        hiding_nonce: &'static str,
+        binding_nonce: &'static str,
+        hiding_nonce_commitment: &'static str,
+        binding_nonce_commitment: &'static str,
+        binding_factor: &'static str,
+        sig_share: &'static str,
+    }
+
+    fn check_rfc_vector<G: FrostGroup>(suite: FrostCiphersuite, generator: &str, vector: &RfcVector) {
+        let scalar = |s: &str| G::deserialize_scalar(&hex::decode(s).unwrap()).unwrap();
+        let group_pubkey = hex::decode(vector.group_pubkey).unwrap();
+        let msg = b"test";
+        let commitments: Vec<_> = vector
+            .signers
+            .iter()
+            .map(|signer| {
+                let commitment =
+                    commitment(signer.identifier, signer.hiding_nonce_commitment, signer.binding_nonce_commitment);
+                assert_eq!(base_mul::<G>(generator, &scalar(signer.hiding_nonce)), commitment.hiding);
+                assert_eq!(base_mul::<G>(generator, &scalar(signer.binding_nonce)), commitment.binding);
+                commitment
+            })
+            .collect();
+        assert_eq!(
+            frost_binding_factors(suite, &group_pubkey, &commitments, msg).unwrap(),
+            vector.signers.iter().map(|signer| hex::decode(signer.binding_factor).unwrap()).collect::<Vec<_>>()
+        );
+
+        let package = SigningPackage::<G>::new(&group_pubkey, &commitments, msg).unwrap();
+        let verifying_shares: Vec<_> =
+            vector.signers.iter().map(|signer| base_mul::<G>(generator, &scalar(signer.participant_share))).collect();
+        let shares: Vec<_> = vector
+            .signers
+            .iter()
+            .enumerate()
+            .map(|(i, signer)| {
+                // z_i = d_i + e_i * rho_i + lambda_i * s_i * c
+                let lambda_s = G::scalar_mul(&package.lagrange_coefficient(i), &scalar(signer.participant_share));
+                let e_rho = G::scalar_mul(&scalar(signer.binding_nonce), &package.binding_factors[i]);
+                let z = G::scalar_add(
+                    &G::scalar_add(&scalar(signer.hiding_nonce), &e_rho),
+                    &G::scalar_mul(&lambda_s, &package.challenge),
+                );
+                let share = FrostSignatureShare { identifier: signer.identifier, share: hex::decode(signer.sig_share).unwrap() };
+                assert_eq!(G::serialize_scalar(&z), share.share);
+                assert_eq!(
+                    frost_verify_signature_share(suite, &group_pubkey, &verifying_shares[i], &commitments, &share, msg),
+                    Ok(())
+                );
+                share
+            })
+            .collect();
+        let verifying_shares: Vec<_> = verifying_shares.iter().map(|share| &share[..]).collect();
+        let signature = frost_aggregate(suite, &group_pubkey, &verifying_shares, &commitments, &shares, msg).unwrap();
+        assert_eq!(signature, hex::decode(vector.sig).unwrap());
+        assert_eq!(frost_verify(suite, &group_pubkey, msg, &signature), Ok(()));
+    }
+
+    #[test]
+    fn ed25519_matches_rfc9591_vectors() {
+        // RFC 9591 Appendix E.1, FROST(Ed25519, SHA-512)
+        check_rfc_vector::<FrostEd25519>(
+            FrostCiphersuite::Ed25519Sha512,
+            ED25519_BASEPOINT,
+            &RfcVector {
+                group_pubkey: "15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673",
+                signers: [
+                    RfcSigner {
+                        identifier: 1,
+                        participant_share: "929dcc590407aae7d388761cddb0c0db6f5627aea8e217f4a033f2ec83d93509",
+                        hiding_nonce: "812d6104142944d5a55924de6d49940956206909f2acaeedecda2b726e630407",
+                        binding_nonce: "b1110165fc2334149750b28dd813a39244f315cff14d4e89e6142f262ed83301",
+                        hiding_nonce_commitment: "b5aa8ab305882a6fc69cbee9327e5a45e54c08af61ae77cb8207be3d2ce13de3",
+                        binding_nonce_commitment: "67e98ab55aa310c3120418e5050c9cf76cf387cb20ac9e4b6fdb6f82a469f932",
+                        binding_factor: "f2cb9d7dd9beff688da6fcc83fa89046b3479417f47f55600b106760eb3b5603",
+                        sig_share: "001719ab5a53ee1a12095cd088fd149702c0720ce5fd2f29dbecf24b7281b603",
+                    },
+                    RfcSigner {
+                        identifier: 3,
+                        participant_share: "d3cb090a075eb154e82fdb4b3cb507f110040905468bb9c46da8bdea643a9a02",
+                        hiding_nonce: "c256de65476204095ebdc01bd11dc10e57b36bc96284595b8215222374f99c0e",
+                        binding_nonce: "243d71944d929063bc51205714ae3c2218bd3451d0214dfb5aeec2a90c35180d",
+                        hiding_nonce_commitment: "cfbdb165bd8aad6eb79deb8d287bcc0ab6658ae57fdcc98ed12c0669e90aec91",
+                        binding_nonce_commitment: "7487bc41a6e712eea2f2af24681b58b1cf1da278ea11fe4e8b78398965f13552",
+                        binding_factor: "b087686bf35a13f3dc78e780a34b0fe8a77fef1b9938c563f5573d71d8d7890f",
+                        sig_share: "bd86125de990acc5e1f13781d8e32c03a9bbd4c53539bbc106058bfd14326007",
+                    },
+                ],
+                sig: "36282629c383bb820a88b71cae937d41f2f2adfcc3d02e55507e2fb9e2dd3cbebd9d2b0844e49ae0f3fa935161e1419aab7b47d21a37ebeae1f17d4987b3160b",
+            },
+        );
+    }
+
+    #[test]
+    fn secp256k1_matches_rfc9591_vectors() {
+        // RFC 9591 Appendix E.5, FROST(secp256k1, SHA-256)
+        check_rfc_vector::<FrostSecp256k1>(
+            FrostCiphersuite::Secp256k1Sha256,
+            SECP256K1_GENERATOR,
+            &RfcVector {
+                group_pubkey: "02f37c34b66ced1fb51c34a90bdae006901f10625cc06c4f64663b0eae87d87b4f",
+                signers: [
+                    RfcSigner {
+                        identifier: 1,
+                        participant_share: "08f89ffe80ac94dcb920c26f3f46140bfc7f95b493f8310f5fc1ea2b01f4254c",
+                        hiding_nonce: "841d3a6450d7580b4da83c8e618414d0f024391f2aeb511d7579224420aa81f0",
+                        binding_nonce: "8d2624f532af631377f33cf44b5ac5f849067cae2eacb88680a31e77c79b5a80",
+                        hiding_nonce_commitment: "03c699af97d26bb4d3f05232ec5e1938c12f1e6ae97643c8f8f11c9820303f1904",
+                        binding_nonce_commitment: "02fa2aaccd51b948c9dc1a325d77226e98a5a3fe65fe9ba213761a60123040a45e",
+                        binding_factor: "3e08fe561e075c653cbfd46908a10e7637c70c74f0a77d5fd45d1a750c739ec6",
+                        sig_share: "c4fce1775a1e141fb579944166eab0d65eefe7b98d480a569bbbfcb14f91c197",
+                    },
+                    RfcSigner {
+                        identifier: 3,
+                        participant_share: "00e95d59dd0d46b0e303e500b62b7ccb0e555d49f5b849f5e748c071da8c0dbc",
+                        hiding_nonce: "2b19b13f193f4ce83a399362a90cdc1e0ddcd83e57089a7af0bdca71d47869b2",
+                        binding_nonce: "7a443bde83dc63ef52dda354005225ba0e553243402a4705ce28ffaafe0f5b98",
+                        hiding_nonce_commitment: "03077507ba327fc074d2793955ef3410ee3f03b82b4cdc2370f71d865beb926ef6",
+                        binding_nonce_commitment: "02ad53031ddfbbacfc5fbda3d3b0c2445c8e3e99cbc4ca2db2aa283fa68525b135",
+                        binding_factor: "93f79041bb3fd266105be251adaeb5fd7f8b104fb554a4ba9a0becea48ddbfd7",
+                        sig_share: "0160fd0d388932f4826d2ebcd6b9eaba734f7c71cf25b4279a4ca2581e47b18d",
+                    },
+                ],
+                sig: "0205b6d04d3774c8929413e3c76024d54149c372d57aae62574ed74319b5ea14d0c65dde8492a7471437e6c2fe3da49b90d23f642b5c6dbe7e36089f096dd97324",
+            },
+        );
+    }
+}
@@ -18,11 +18,13 @@
 mod bls;
 mod ecdsa;
 mod eddsa;
+mod frost;
 mod schnorr;
 
 pub use bls::*;
 pub use ecdsa::*;
 pub use eddsa::*;
+pub use frost::*;
 pub use schnorr::*;
 pub use sp_io::hashing::sha2_256;
 use sp_core::bounded::alloc::vec::Vec;
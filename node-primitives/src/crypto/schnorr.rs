@@ -15,24 +15,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_core::bounded::alloc::{string::{ToString, String}, format};
+use sp_core::bounded::alloc::{format, string::{String, ToString}, vec::Vec};
 use core::ops::Neg;
-use chain_bridge::utils::disintegrate_btc_msgs_and_sigs;
-use secp256k1::curve::{Affine, Jacobian, Scalar, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+use crate::{chains::btc::disintegrate_btc_schnorr_verify_data, VerifyError};
+use secp256k1::curve::{Affine, Jacobian, Scalar, AFFINE_G, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
 use secp256k1::{Error as ECError, PublicKey as ECPK, PublicKeyFormat, SecretKey as ECSK};
 use sha2::{Digest, Sha256};
 
-pub fn sr25519_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
+pub fn sr25519_verify(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
     use sp_core::sr25519::{Public, Signature};
 
-    let pk = Public::try_from(pubkey).map_err(|_| "sr25519 pubkey from bytes".to_string())?;
-    let signature = Signature::try_from(sig).map_err(|_| "sr25519 signature from bytes".to_string())?;
+    let pk = Public::try_from(pubkey).map_err(|_| VerifyError::InvalidPublicKeyLength {
+        expected: 32,
+        actual: pubkey.len() as u32,
+    })?;
+    let signature = Signature::try_from(sig).map_err(|_| VerifyError::InvalidSignatureLength {
+        expected: 64,
+        actual: sig.len() as u32,
+    })?;
     if !sp_io::crypto::sr25519_verify(&signature, msg, &pk) {
-        return Err("verify sr25519 signature failed".to_string());
+        return Err(VerifyError::BadSignature);
     }
     Ok(())
 }
 
+// Schnorr over secp256k1 as produced by the TSS nodes ("sr_secp256k1"). Unlike BIP340 the
+// points are not x-only and the challenge is untagged:
+//
+//   pubkey     X = x * G, 33-byte compressed or 65-byte uncompressed SEC1
+//   signature  s (32-byte big endian scalar) || R (33-byte compressed SEC1 point)
+//   challenge  e = int(SHA256(R || X || m)) mod n, R and X compressed
+//   valid iff  s * G == R + e * X, with 0 < s < n and R, X not the identity
+//
+// The signer picks k, sets R = k * G and s = k + e * x mod n.
+
 // H(R, X, m)
 fn sr_secp256k1_hash(r: &[u8], x: &[u8], m: &[u8]) -> Scalar {
     let mut hasher = Sha256::new();
@@ -50,13 +66,21 @@ fn sr_secp256k1_hash(r: &[u8], x: &[u8], m: &[u8]) -> Scalar {
     h
 }
 
-pub fn sr_secp256k1_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+/// Verify an sr_secp256k1 schnorr signature, see the scheme description above.
+pub fn sr_secp256k1_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
     if signature.len() != 65 {
-        return Err("invalid length of sr secp256k1 signature".to_string());
+        return Err(VerifyError::InvalidSignatureLength { expected: 65, actual: signature.len() as u32 });
     }
-    let r: ECSK = ECSK::parse_slice(&signature[..32]).map_err(|e| format!("failed to parse r from signature: {:?}", e))?;
-    let v: ECPK = ECPK::parse_slice(&signature[32..], Some(PublicKeyFormat::Compressed)).map_err(|e| format!("failed to parse v from signature: {:?}", e))?;
-    let pk: ECPK = ECPK::parse_slice(pubkey, Some(PublicKeyFormat::Full)).map_err(|e| format!("failed to parse public key: {:?}, error: {:?}", pubkey, e))?;
+    let format = match pubkey.len() {
+        33 => PublicKeyFormat::Compressed,
+        65 => PublicKeyFormat::Full,
+        _ => return Err(VerifyError::InvalidPublicKeyLength { expected: 33, actual: pubkey.len() as u32 }),
+    };
+    // `SecretKey` only holds scalars in [1, n), which rules out s = 0 and s >= n.
+    let s: ECSK = ECSK::parse_slice(&signature[..32]).map_err(|_| VerifyError::InvalidSignature)?;
+    // Points parsed from SEC1 encodings are always on the curve and never the identity.
+    let v: ECPK = ECPK::parse_slice(&signature[32..], Some(PublicKeyFormat::Compressed)).map_err(|_| VerifyError::InvalidSignature)?;
+    let pk: ECPK = ECPK::parse_slice(pubkey, Some(format)).map_err(|_| VerifyError::InvalidPublicKey)?;
     let e = sr_secp256k1_hash(
         &v.serialize_compressed(),
         &pk.serialize_compressed(),
@@ -66,20 +90,52 @@ pub fn sr_secp256k1_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> R
     ECMULT_CONTEXT.ecmult_const(&mut e_y_j, &pk.into(), &e);
 
     let e_y_plus_v_j = e_y_j.add_ge(&v.into());
+    if e_y_plus_v_j.is_infinity() {
+        return Err(VerifyError::BadSignature);
+    }
 
     let mut g_j_s = Jacobian::default();
-    ECMULT_GEN_CONTEXT.ecmult_gen(&mut g_j_s, &r.into());
+    ECMULT_GEN_CONTEXT.ecmult_gen(&mut g_j_s, &s.into());
 
     let g_s = Affine::from_gej(&g_j_s);
     let e_y_plus_v: Affine = Affine::from_gej(&e_y_plus_v_j);
 
     // R + H(R,X,m) * X = s * G
     if e_y_plus_v != g_s {
-        return Err("verify sr secp256k1 signature failed".to_string());
+        return Err(VerifyError::BadSignature);
     }
     Ok(())
 }
 
+/// Sign `message` with the sr_secp256k1 scheme, returning `s || R`.
+///
+/// The nonce is derived from `SHA256(seckey || aux_rand || message)`, so signing is
+/// deterministic for a given `aux_rand`.
+pub fn sr_secp256k1_sign(seckey: &[u8; 32], message: &[u8], aux_rand: &[u8; 32]) -> Result<[u8; 65], String> {
+    let sk = ECSK::parse(seckey).map_err(|_| "invalid sr_secp256k1 secret key".to_string())?;
+    let x = ECPK::from_secret_key(&sk).serialize_compressed();
+
+    let mut hasher = Sha256::new();
+    hasher.update(seckey);
+    hasher.update(aux_rand);
+    hasher.update(message);
+    let mut nonce = [0u8; 32];
+    nonce.copy_from_slice(&hasher.finalize()[..]);
+    let mut k = Scalar::default();
+    let _ = k.set_b32(&nonce);
+    let k_sk = ECSK::parse(&k.b32()).map_err(|_| "sr_secp256k1 nonce is zero".to_string())?;
+    let r = ECPK::from_secret_key(&k_sk).serialize_compressed();
+
+    let e = sr_secp256k1_hash(&r, &x, message);
+    let d: Scalar = sk.into();
+    let mut signature = [0u8; 65];
+    signature[..32].copy_from_slice(&(k + e * d).b32());
+    signature[32..].copy_from_slice(&r);
+    sr_secp256k1_verify(&x, message, &signature)
+        .map_err(|e| format!("sr_secp256k1 signature self check failed: {e}"))?;
+    Ok(signature)
+}
+
 // SHA256 (SHA256("BIP0340/challenge")||SHA256("BIP0340/challenge")||R.x||P.x||M)
 pub fn bitcoin_sha256_tagged(r_x: &[u8], p_x: &[u8], m: &[u8]) -> Scalar {
     // SHA256("BIP0340/challenge")
@@ -117,16 +173,16 @@ pub fn load_xonly_pubkey(pubkey: &[u8]) -> Result<ECPK, ECError> {
 }
 
 // https://github.com/joschisan/schnorr_secp256k1/blob/main/src/schnorr.rs#LL90C1-L90C1
-pub fn btc_schnorr_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+pub fn btc_schnorr_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
     if signature.len() != 64 {
-        return Err("invalid length of btc schnorr signature".to_string());
+        return Err(VerifyError::InvalidSignatureLength { expected: 64, actual: signature.len() as u32 });
     }
     if pubkey.len() != 32 {
-        return Err("invalid length of btc schnorr public key".to_string());
+        return Err(VerifyError::InvalidPublicKeyLength { expected: 32, actual: pubkey.len() as u32 });
     }
-    let p: ECPK = load_xonly_pubkey(&pubkey).map_err(|e| e.to_string())?;
-    let r: ECPK = load_xonly_pubkey(&signature[..32]).map_err(|e| e.to_string())?;
-    let s: ECSK = ECSK::parse_slice(&signature[32..]).map_err(|e| e.to_string())?;
+    let p: ECPK = load_xonly_pubkey(&pubkey).map_err(|_| VerifyError::InvalidPublicKey)?;
+    let r: ECPK = load_xonly_pubkey(&signature[..32]).map_err(|_| VerifyError::InvalidSignature)?;
+    let s: ECSK = ECSK::parse_slice(&signature[32..]).map_err(|_| VerifyError::InvalidSignature)?;
 
     // compute e
     let e = bitcoin_sha256_tagged(&signature[..32], &pubkey, message);
@@ -142,42 +198,897 @@ pub fn btc_schnorr_verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Re
     let rj = e_p_j.add_var(&g_j_s, None);
     let mut rx = Affine::from_gej(&rj);
     if rx.is_infinity() {
-        return Err("verify btc schnorr signature failed for rx is infinity".to_string());
+        return Err(VerifyError::BadSignature);
     }
 
     rx.x.normalize_var();
     rx.y.normalize_var();
     let r: Affine = r.into();
 
-    if rx.y.is_odd() {
-        return Err("verify btc schnorr signature failed for rx.y is odd".to_string());
-    }
-    if rx.x != r.x {
-        return Err("verify btc schnorr signature failed for rx.x is not eq to r.x".to_string());
+    if rx.y.is_odd() || rx.x != r.x {
+        return Err(VerifyError::BadSignature);
     }
     return Ok(())
 }
 
-pub fn verify_btc_schnorr(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), String> {
-    let (msgs, sigs) = match disintegrate_btc_msgs_and_sigs(msg, sig, false) {
-        Some(data) => data,
-        None => {
-            return Err("disintegrate btc msgs and sigs failed".to_string());
+// SHA256(SHA256(tag)||SHA256(tag)||data)
+fn bip340_tagged_hash(tag: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let tag = Sha256::digest(tag);
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    for d in data {
+        hasher.update(d);
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hasher.finalize()[..]);
+    out
+}
+
+/// BIP340 x-only public key of a secret key.
+pub fn btc_schnorr_xonly_pubkey(seckey: &[u8; 32]) -> Result<[u8; 32], String> {
+    let sk = ECSK::parse(seckey).map_err(|_| "invalid btc schnorr secret key".to_string())?;
+    let mut xonly = [0u8; 32];
+    xonly.copy_from_slice(&ECPK::from_secret_key(&sk).serialize_compressed()[1..]);
+    Ok(xonly)
+}
+
+/// BIP340 signature of `message` with auxiliary randomness `aux_rand`.
+///
+/// Deterministic for a given `aux_rand`. Use fresh randomness in production, a
+/// fixed value (e.g. all zero) still gives secure signatures and reproducible tests.
+pub fn btc_schnorr_sign(seckey: &[u8; 32], message: &[u8], aux_rand: &[u8; 32]) -> Result<[u8; 64], String> {
+    let sk = ECSK::parse(seckey).map_err(|_| "invalid btc schnorr secret key".to_string())?;
+    let p = ECPK::from_secret_key(&sk).serialize_compressed();
+    let mut d: Scalar = sk.into();
+    if p[0] == 0x03 {
+        d = d.neg();
+    }
+
+    let mask = bip340_tagged_hash(b"BIP0340/aux", &[aux_rand]);
+    let mut t = d.b32();
+    t.iter_mut().zip(mask).for_each(|(t, m)| *t ^= m);
+    let rand = bip340_tagged_hash(b"BIP0340/nonce", &[&t, &p[1..], message]);
+    let mut k = Scalar::default();
+    let _ = k.set_b32(&rand);
+    let k_sk = ECSK::parse(&k.b32()).map_err(|_| "btc schnorr nonce is zero".to_string())?;
+    let r = ECPK::from_secret_key(&k_sk).serialize_compressed();
+    if r[0] == 0x03 {
+        k = k.neg();
+    }
+
+    let e = bitcoin_sha256_tagged(&r[1..], &p[1..], message);
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&r[1..]);
+    signature[32..].copy_from_slice(&(k + e * d).b32());
+    btc_schnorr_verify(&p[1..], message, &signature)
+        .map_err(|e| format!("btc schnorr signature self check failed: {e}"))?;
+    Ok(signature)
+}
+
+/// Verify BIP340 signatures `(pubkey, message, signature)` as one batch.
+///
+/// With `R_i`, `P_i` lifted from `r_i` and the x-only key, `e_i` the BIP340 challenge and
+/// coefficients `a_0 = 1, a_1..a_n` derived from a hash over the whole batch, checks
+/// `(sum a_i * s_i) * G == sum a_i * R_i + sum (a_i * e_i) * P_i` with a single
+/// multi-scalar multiplication. If the batch equation fails every signature is verified
+/// on its own to report the first failing index. An empty batch is `InvalidMessage`, there
+/// is nothing it could vouch for.
+pub fn btc_schnorr_batch_verify(items: &[(&[u8], &[u8], &[u8])]) -> Result<(), VerifyError> {
+    if items.is_empty() {
+        return Err(VerifyError::InvalidMessage);
+    }
+    if items.len() == 1 {
+        let (pubkey, message, signature) = items[0];
+        return btc_schnorr_verify(pubkey, message, signature).map_err(|e| VerifyError::at_index(0, e));
+    }
+
+    let mut seed = Sha256::new();
+    seed.update(Sha256::digest(b"BIP0340/batch"));
+    for (pubkey, message, signature) in items {
+        seed.update(pubkey);
+        seed.update((message.len() as u32).to_le_bytes());
+        seed.update(message);
+        seed.update(signature);
+    }
+    let seed = seed.finalize();
+
+    // distinct public keys share one point and one summed scalar
+    let mut keys: Vec<(&[u8], Affine, Scalar)> = Vec::new();
+    let mut terms: Vec<(Affine, Scalar)> = Vec::with_capacity(items.len() + 1);
+    let mut s_sum = Scalar::default();
+    for (i, (pubkey, message, signature)) in items.iter().enumerate() {
+        let at = |e| VerifyError::at_index(i, e);
+        if signature.len() != 64 {
+            return Err(at(VerifyError::InvalidSignatureLength { expected: 64, actual: signature.len() as u32 }));
+        }
+        if pubkey.len() != 32 {
+            return Err(at(VerifyError::InvalidPublicKeyLength { expected: 32, actual: pubkey.len() as u32 }));
+        }
+        let r: ECPK = load_xonly_pubkey(&signature[..32]).map_err(|_| at(VerifyError::InvalidSignature))?;
+        let s: ECSK = ECSK::parse_slice(&signature[32..]).map_err(|_| at(VerifyError::InvalidSignature))?;
+        let coefficient = if i == 0 { Scalar::from_int(1) } else { batch_coefficient(&seed, i as u32) };
+        let e = bitcoin_sha256_tagged(&signature[..32], pubkey, message);
+
+        s_sum += coefficient.clone() * s.into();
+        terms.push((r.into(), coefficient.clone()));
+        match keys.iter_mut().find(|(key, _, _)| key == pubkey) {
+            Some((_, _, scalar)) => *scalar += coefficient * e,
+            None => {
+                let p: ECPK = load_xonly_pubkey(pubkey).map_err(|_| at(VerifyError::InvalidPublicKey))?;
+                keys.push((pubkey, p.into(), coefficient * e));
+            }
+        }
+    }
+    terms.extend(keys.into_iter().map(|(_, point, scalar)| (point, scalar)));
+    terms.push((AFFINE_G.clone(), s_sum.neg()));
+
+    if multi_scalar_mul(&terms).is_infinity() {
+        return Ok(());
+    }
+    for (i, (pubkey, message, signature)) in items.iter().enumerate() {
+        btc_schnorr_verify(pubkey, message, signature).map_err(|e| VerifyError::at_index(i, e))?;
+    }
+    Err(VerifyError::BadSignature)
+}
+
+fn batch_coefficient(seed: &[u8], index: u32) -> Scalar {
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update(index.to_le_bytes());
+    let mut bin = [0u8; 32];
+    bin.copy_from_slice(&hasher.finalize()[..]);
+    let mut a = Scalar::default();
+    let _ = a.set_b32(&bin);
+    a
+}
+
+const WNAF_WINDOW: usize = 5;
+
+/// Width-w NAF of `scalar`, digits odd in `(-2^(w-1), 2^(w-1))` and at least w apart.
+fn wnaf(scalar: &Scalar) -> [i32; 256] {
+    let mut wnaf = [0i32; 256];
+    let mut s = scalar.clone();
+    let mut sign = 1;
+    if s.bits(255, 1) > 0 {
+        s = s.neg();
+        sign = -1;
+    }
+    let mut carry = 0;
+    let mut bit = 0;
+    while bit < 256 {
+        if s.bits(bit, 1) == carry as u32 {
+            bit += 1;
+            continue;
+        }
+        let now = WNAF_WINDOW.min(256 - bit);
+        let mut word = s.bits_var(bit, now) as i32 + carry;
+        carry = (word >> (WNAF_WINDOW - 1)) & 1;
+        word -= carry << WNAF_WINDOW;
+        wnaf[bit] = sign * word;
+        bit += now;
+    }
+    wnaf
+}
+
+/// `sum scalar_i * point_i` by Straus' method, sharing the doublings across all terms.
+fn multi_scalar_mul(terms: &[(Affine, Scalar)]) -> Jacobian {
+    let table_size = 1 << (WNAF_WINDOW - 2);
+    let mut tables = Vec::with_capacity(terms.len());
+    let mut wnafs = Vec::with_capacity(terms.len());
+    for (point, scalar) in terms {
+        // odd multiples P, 3P, .., (2^(w-1) - 1)P
+        let p = Jacobian::from_ge(point);
+        let p2 = p.double_var(None);
+        let mut table = Vec::with_capacity(table_size);
+        table.push(p);
+        for k in 1..table_size {
+            let next = table[k - 1].add_var(&p2, None);
+            table.push(next);
+        }
+        tables.push(table);
+        wnafs.push(wnaf(scalar));
+    }
+
+    let mut r = Jacobian::default();
+    r.set_infinity();
+    for bit in (0..256).rev() {
+        r = r.double_var(None);
+        for (table, wnaf) in tables.iter().zip(&wnafs) {
+            let digit = wnaf[bit];
+            if digit > 0 {
+                r = r.add_var(&table[(digit / 2) as usize], None);
+            } else if digit < 0 {
+                r = r.add_var(&table[(-digit / 2) as usize].neg(), None);
+            }
+        }
+    }
+    r
+}
+
+// Compressed SEC1 encoding, `None` for the point at infinity.
+fn serialize_point(point: &Jacobian) -> Option<[u8; 33]> {
+    if point.is_infinity() {
+        return None;
+    }
+    let mut affine = Affine::from_gej(point);
+    affine.x.normalize_var();
+    affine.y.normalize_var();
+    let mut out = [0u8; 33];
+    out[0] = if affine.y.is_odd() { 0x03 } else { 0x02 };
+    out[1..].copy_from_slice(&affine.x.b32());
+    Some(out)
+}
+
+fn parse_point(bytes: &[u8]) -> Option<Jacobian> {
+    let pk = ECPK::parse_slice(bytes, Some(PublicKeyFormat::Compressed)).ok()?;
+    let affine: Affine = pk.into();
+    Some(Jacobian::from_ge(&affine))
+}
+
+fn hash_to_scalar(hash: &[u8; 32]) -> Scalar {
+    let mut scalar = Scalar::default();
+    let _ = scalar.set_b32(hash);
+    scalar
+}
+
+// na * a + ng * G
+fn ecmult(a: &Jacobian, na: &Scalar, ng: &Scalar) -> Jacobian {
+    let mut r = Jacobian::default();
+    ECMULT_CONTEXT.ecmult(&mut r, a, na, ng);
+    r
+}
+
+/// Output of BIP327 `KeyAgg`, optionally tweaked.
+///
+/// `pubkey`/`xonly_pubkey` give the aggregate key; untweaked it can serve as a Taproot
+/// internal key, after [`MusigKeyAggContext::apply_taproot_tweak`] it is the output key.
+#[derive(Clone, Debug)]
+pub struct MusigKeyAggContext {
+    pubkeys: Vec<[u8; 33]>,
+    keys_hash: [u8; 32],
+    second_key: Option<[u8; 33]>,
+    q: Jacobian,
+    gacc: Scalar,
+    tacc: Scalar,
+}
+
+/// BIP327 `KeySort`, lexicographic order of the compressed keys.
+pub fn musig_key_sort(pubkeys: &[[u8; 33]]) -> Vec<[u8; 33]> {
+    let mut sorted = pubkeys.to_vec();
+    sorted.sort();
+    sorted
+}
+
+/// BIP327 `KeyAgg`. Keys are aggregated in the given order, sort them with
+/// [`musig_key_sort`] first to make the aggregate key order independent.
+pub fn musig_key_agg(pubkeys: &[[u8; 33]]) -> Result<MusigKeyAggContext, VerifyError> {
+    if pubkeys.is_empty() {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    let points = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, pk)| parse_point(pk).ok_or(VerifyError::at_index(i, VerifyError::InvalidPublicKey)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let keys_hash = bip340_tagged_hash(b"KeyAgg list", &pubkeys.iter().map(|pk| &pk[..]).collect::<Vec<_>>());
+    let second_key = pubkeys.iter().find(|pk| *pk != &pubkeys[0]).copied();
+    let mut ctx = MusigKeyAggContext {
+        pubkeys: pubkeys.to_vec(),
+        keys_hash,
+        second_key,
+        q: Jacobian::default(),
+        gacc: Scalar::from_int(1),
+        tacc: Scalar::from_int(0),
+    };
+
+    let mut q = Jacobian::default();
+    q.set_infinity();
+    for (pk, point) in pubkeys.iter().zip(&points) {
+        q = q.add_var(&ecmult(point, &ctx.coefficient(pk), &Scalar::from_int(0)), None);
+    }
+    if q.is_infinity() {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    ctx.q = q;
+    Ok(ctx)
+}
+
+impl MusigKeyAggContext {
+    /// Compressed aggregate key.
+    pub fn pubkey(&self) -> [u8; 33] {
+        serialize_point(&self.q).expect("aggregate key is never infinity; qed")
+    }
+
+    /// X-only aggregate key, the key final signatures verify against with `btc_schnorr_verify`.
+    pub fn xonly_pubkey(&self) -> [u8; 32] {
+        let mut xonly = [0u8; 32];
+        xonly.copy_from_slice(&self.pubkey()[1..]);
+        xonly
+    }
+
+    // KeyAggCoeff, 1 for the second distinct key
+    fn coefficient(&self, pubkey: &[u8; 33]) -> Scalar {
+        if self.second_key.as_ref() == Some(pubkey) {
+            return Scalar::from_int(1);
+        }
+        hash_to_scalar(&bip340_tagged_hash(b"KeyAgg coefficient", &[&self.keys_hash, pubkey]))
+    }
+
+    fn has_even_y(&self) -> bool {
+        self.pubkey()[0] == 0x02
+    }
+
+    /// BIP327 `ApplyTweak`, a plain tweak for BIP32 derivation or an x-only tweak for Taproot.
+    pub fn apply_tweak(&mut self, tweak: &[u8; 32], is_xonly: bool) -> Result<(), VerifyError> {
+        let mut t = Scalar::default();
+        if bool::from(t.set_b32(tweak)) {
+            return Err(VerifyError::InvalidPublicKey);
+        }
+        let g = if is_xonly && !self.has_even_y() { Scalar::from_int(1).neg() } else { Scalar::from_int(1) };
+        let q = ecmult(&self.q, &g, &t);
+        if q.is_infinity() {
+            return Err(VerifyError::InvalidPublicKey);
+        }
+        self.q = q;
+        self.tacc = t + g.clone() * self.tacc.clone();
+        self.gacc = g * self.gacc.clone();
+        Ok(())
+    }
+
+    /// Apply the BIP341 tweak `hash_TapTweak(internal key || merkle root)`, `None` for a key
+    /// path only output.
+    pub fn apply_taproot_tweak(&mut self, merkle_root: Option<&[u8; 32]>) -> Result<(), VerifyError> {
+        let tweak = btc_taproot_tweak_hash(&self.xonly_pubkey(), merkle_root);
+        self.apply_tweak(&tweak, true)
+    }
+}
+
+fn btc_taproot_tweak_hash(internal_key: &[u8; 32], merkle_root: Option<&[u8; 32]>) -> [u8; 32] {
+    match merkle_root {
+        Some(root) => bip340_tagged_hash(b"TapTweak", &[internal_key, root]),
+        None => bip340_tagged_hash(b"TapTweak", &[internal_key]),
+    }
+}
+
+/// BIP341 output key of the x-only `internal_key` committing to `merkle_root`.
+pub fn btc_taproot_output_key(internal_key: &[u8], merkle_root: Option<&[u8; 32]>) -> Result<[u8; 32], VerifyError> {
+    if internal_key.len() != 32 {
+        return Err(VerifyError::InvalidPublicKeyLength { expected: 32, actual: internal_key.len() as u32 });
+    }
+    let p: ECPK = load_xonly_pubkey(internal_key).map_err(|_| VerifyError::InvalidPublicKey)?;
+    let mut key = [0u8; 32];
+    key.copy_from_slice(internal_key);
+    let mut t = Scalar::default();
+    if bool::from(t.set_b32(&btc_taproot_tweak_hash(&key, merkle_root))) {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    let affine: Affine = p.into();
+    let q = serialize_point(&ecmult(&Jacobian::from_ge(&affine), &Scalar::from_int(1), &t)).ok_or(VerifyError::InvalidPublicKey)?;
+    key.copy_from_slice(&q[1..]);
+    Ok(key)
+}
+
+/// BIP327 `NonceAgg` of 66-byte public nonces `R1 || R2`.
+pub fn musig_nonce_agg(pubnonces: &[[u8; 66]]) -> Result<[u8; 66], VerifyError> {
+    let mut aggnonce = [0u8; 66];
+    for j in 0..2 {
+        let mut r = Jacobian::default();
+        r.set_infinity();
+        for (i, pubnonce) in pubnonces.iter().enumerate() {
+            let point = parse_point(&pubnonce[33 * j..33 * (j + 1)])
+                .ok_or(VerifyError::at_index(i, VerifyError::InvalidCommitment))?;
+            r = r.add_var(&point, None);
+        }
+        // the infinity is encoded as 33 zero bytes
+        if let Some(bytes) = serialize_point(&r) {
+            aggnonce[33 * j..33 * (j + 1)].copy_from_slice(&bytes);
+        }
+    }
+    Ok(aggnonce)
+}
+
+/// Values every signer derives from the aggregate nonce and message.
+struct MusigSession {
+    b: Scalar,
+    r: [u8; 33],
+    e: Scalar,
+}
+
+fn musig_session(ctx: &MusigKeyAggContext, aggnonce: &[u8; 66], msg: &[u8]) -> Result<MusigSession, VerifyError> {
+    let parse_ext = |bytes: &[u8]| -> Result<Jacobian, VerifyError> {
+        if bytes.iter().all(|b| *b == 0) {
+            let mut infinity = Jacobian::default();
+            infinity.set_infinity();
+            return Ok(infinity);
         }
+        parse_point(bytes).ok_or(VerifyError::InvalidCommitment)
+    };
+    let r1 = parse_ext(&aggnonce[..33])?;
+    let r2 = parse_ext(&aggnonce[33..])?;
+    let q = ctx.xonly_pubkey();
+    let b = hash_to_scalar(&bip340_tagged_hash(b"MuSig/noncecoef", &[aggnonce, &q, msg]));
+    let r = match r2.is_infinity() {
+        true => r1,
+        false => ecmult(&r2, &b, &Scalar::from_int(0)).add_var(&r1, None),
     };
-    if msgs.len() != sigs.len() {
-        return Err("invalid length for btc msgs and sigs".to_string());
+    // an infinite R falls back to G, which no honest signer can make happen
+    let r = serialize_point(&r)
+        .or_else(|| serialize_point(&Jacobian::from_ge(&AFFINE_G)))
+        .expect("generator is not infinity; qed");
+    let e = bitcoin_sha256_tagged(&r[1..], &q, msg);
+    Ok(MusigSession { b, r, e })
+}
+
+/// BIP327 `PartialSigVerifyInternal`: check the partial signature `psig` of the signer with
+/// `pubkey` and `pubnonce`, so the coordinator can blame a signer before aggregating.
+pub fn musig_partial_sig_verify(
+    ctx: &MusigKeyAggContext,
+    aggnonce: &[u8; 66],
+    msg: &[u8],
+    pubkey: &[u8; 33],
+    pubnonce: &[u8; 66],
+    psig: &[u8],
+) -> Result<(), VerifyError> {
+    if psig.len() != 32 {
+        return Err(VerifyError::InvalidSignatureLength { expected: 32, actual: psig.len() as u32 });
+    }
+    let mut s = Scalar::default();
+    if bool::from(s.set_b32(psig.try_into().expect("length checked above; qed"))) {
+        return Err(VerifyError::InvalidSignature);
+    }
+    if !ctx.pubkeys.contains(pubkey) {
+        return Err(VerifyError::InvalidPublicKey);
+    }
+    let p = parse_point(pubkey).ok_or(VerifyError::InvalidPublicKey)?;
+    let r1 = parse_point(&pubnonce[..33]).ok_or(VerifyError::InvalidCommitment)?;
+    let r2 = parse_point(&pubnonce[33..]).ok_or(VerifyError::InvalidCommitment)?;
+    let session = musig_session(ctx, aggnonce, msg)?;
+
+    // s * G == Re + (e * a * g * gacc) * P, with Re = R1 + b * R2 negated when R has odd y
+    let mut re = ecmult(&r2, &session.b, &Scalar::from_int(0)).add_var(&r1, None);
+    if session.r[0] == 0x03 {
+        re = re.neg();
+    }
+    let g = if ctx.has_even_y() { Scalar::from_int(1) } else { Scalar::from_int(1).neg() };
+    let coefficient = session.e * ctx.coefficient(pubkey) * g * ctx.gacc.clone();
+    if !ecmult(&p, &coefficient, &s.neg()).add_var(&re, None).is_infinity() {
+        return Err(VerifyError::BadSignature);
     }
+    Ok(())
+}
+
+/// BIP327 `PartialSigAgg`, a BIP340 signature valid for `ctx.xonly_pubkey()`.
+pub fn musig_partial_sig_agg(
+    ctx: &MusigKeyAggContext,
+    aggnonce: &[u8; 66],
+    msg: &[u8],
+    psigs: &[&[u8]],
+) -> Result<[u8; 64], VerifyError> {
+    let session = musig_session(ctx, aggnonce, msg)?;
+    let mut s = Scalar::from_int(0);
+    for (i, psig) in psigs.iter().enumerate() {
+        let bytes: &[u8; 32] = (*psig).try_into().map_err(|_| {
+            VerifyError::at_index(i, VerifyError::InvalidSignatureLength { expected: 32, actual: psig.len() as u32 })
+        })?;
+        let mut s_i = Scalar::default();
+        if bool::from(s_i.set_b32(bytes)) {
+            return Err(VerifyError::at_index(i, VerifyError::InvalidSignature));
+        }
+        s += s_i;
+    }
+    let g = if ctx.has_even_y() { Scalar::from_int(1) } else { Scalar::from_int(1).neg() };
+    s += session.e * g * ctx.tacc.clone();
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&session.r[1..]);
+    signature[32..].copy_from_slice(&s.b32());
+    Ok(signature)
+}
+
+pub fn verify_btc_schnorr(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyError> {
+    let (msgs, sigs) = disintegrate_btc_schnorr_verify_data(msg, sig)?;
     let pubkey = match pubkey.len() {
         33 | 65 => &pubkey[1..33],
         _ => &pubkey
     };
-    for i in 0..msgs.len() {
-        btc_schnorr_verify(
-            pubkey,
-            &msgs[i],
-            &sigs[i],
-        ).map_err(|e| format!("btc signature verify failed: {e:?} for index: {i:?}"))?;
+    let items: Vec<(&[u8], &[u8], &[u8])> = msgs
+        .iter()
+        .zip(&sigs)
+        .map(|(msg, sig)| (pubkey, &msg[..], &sig[..]))
+        .collect();
+    btc_schnorr_batch_verify(&items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+    const BIP340_VECTORS: &str = "\
+index,secret key,public key,aux_rand,message,signature,verification result,comment
+0,0000000000000000000000000000000000000000000000000000000000000003,F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9,0000000000000000000000000000000000000000000000000000000000000000,0000000000000000000000000000000000000000000000000000000000000000,E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0,TRUE,
+1,B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,0000000000000000000000000000000000000000000000000000000000000001,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A,TRUE,
+2,C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9,DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8,C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906,7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C,5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7,TRUE,
+3,0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710,25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF,7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3,TRUE,test fails if msg is reduced modulo p or n
+4,,D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9,,4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703,00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4,TRUE,
+5,,EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,public key not on the curve
+6,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2,FALSE,has_even_y(R) is false
+7,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD,FALSE,negated message
+8,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6,FALSE,negated s value
+9,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051,FALSE,sG - eP is infinite. Test fails in single verification if has_even_y(inf) is defined as true and x(inf) as 0
+10,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197,FALSE,sG - eP is infinite. Test fails in single verification if has_even_y(inf) is defined as true and x(inf) as 1
+11,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,sig[0:32] is not an X coordinate on the curve
+12,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,sig[0:32] is equal to field size
+13,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141,FALSE,sig[32:64] is equal to curve order
+14,,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,public key is not a valid X coordinate because it exceeds the field size
+15,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,,71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63,TRUE,message of size 0 (added 2022-12)
+16,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,11,08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF,TRUE,message of size 1 (added 2022-12)
+17,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,0102030405060708090A0B0C0D0E0F1011,5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5,TRUE,message of size 17 (added 2022-12)
+18,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,99999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999,403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367,TRUE,message of size 100 (added 2022-12)
+";
+
+    struct Bip340Vector {
+        index: usize,
+        seckey: Option<[u8; 32]>,
+        pubkey: Vec<u8>,
+        aux_rand: Option<[u8; 32]>,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        valid: bool,
+    }
+
+    fn bip340_vectors() -> Vec<Bip340Vector> {
+        let array = |field: &str| -> Option<[u8; 32]> {
+            (!field.is_empty()).then(|| hex::decode(field).unwrap().try_into().unwrap())
+        };
+        BIP340_VECTORS
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let fields: Vec<&str> = line.splitn(8, ',').collect();
+                Bip340Vector {
+                    index: fields[0].parse().unwrap(),
+                    seckey: array(fields[1]),
+                    pubkey: hex::decode(fields[2]).unwrap(),
+                    aux_rand: array(fields[3]),
+                    message: hex::decode(fields[4]).unwrap(),
+                    signature: hex::decode(fields[5]).unwrap(),
+                    valid: fields[6] == "TRUE",
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bip340_vectors_verify() {
+        let vectors = bip340_vectors();
+        assert_eq!(vectors.len(), 19);
+        for v in &vectors {
+            let result = btc_schnorr_verify(&v.pubkey, &v.message, &v.signature);
+            assert_eq!(result.is_ok(), v.valid, "vector {}: {:?}", v.index, result);
+        }
+    }
+
+    #[test]
+    fn bip340_vectors_sign() {
+        for v in bip340_vectors() {
+            let (Some(seckey), Some(aux_rand)) = (v.seckey, v.aux_rand) else { continue };
+            assert_eq!(btc_schnorr_xonly_pubkey(&seckey).unwrap().to_vec(), v.pubkey, "vector {}", v.index);
+            let signature = btc_schnorr_sign(&seckey, &v.message, &aux_rand).unwrap();
+            assert_eq!(signature.to_vec(), v.signature, "vector {}", v.index);
+        }
+    }
+
+    #[test]
+    fn bip340_sign_rejects_invalid_secret_keys() {
+        let order: [u8; 32] = hex::decode("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141").unwrap().try_into().unwrap();
+        for seckey in [[0u8; 32], order, [0xff; 32]] {
+            assert!(btc_schnorr_sign(&seckey, b"msg", &[0u8; 32]).is_err());
+            assert!(btc_schnorr_xonly_pubkey(&seckey).is_err());
+        }
+    }
+
+    fn sign(seckey: &[u8; 32], msg: &[u8]) -> ([u8; 32], [u8; 64]) {
+        let pubkey = btc_schnorr_xonly_pubkey(seckey).unwrap();
+        (pubkey, btc_schnorr_sign(seckey, msg, &[0u8; 32]).unwrap())
+    }
+
+    fn batch(count: usize, keys: usize) -> Vec<([u8; 32], [u8; 32], [u8; 64])> {
+        (0..count)
+            .map(|i| {
+                let mut seckey = [0u8; 32];
+                seckey[31] = (i % keys) as u8 + 1;
+                seckey[0] = 0x5a;
+                let msg: [u8; 32] = Sha256::digest(&(i as u32).to_le_bytes()).into();
+                let (pubkey, sig) = sign(&seckey, &msg);
+                (pubkey, msg, sig)
+            })
+            .collect()
+    }
+
+    fn items(batch: &[([u8; 32], [u8; 32], [u8; 64])]) -> Vec<(&[u8], &[u8], &[u8])> {
+        batch.iter().map(|(p, m, s)| (&p[..], &m[..], &s[..])).collect()
+    }
+
+    #[test]
+    fn batch_verify_accepts_valid_signatures() {
+        for (count, keys) in [(1, 1), (2, 1), (7, 3), (40, 40)] {
+            let batch = batch(count, keys);
+            for (p, m, s) in &batch {
+                assert_eq!(btc_schnorr_verify(p, m, s), Ok(()));
+            }
+            assert_eq!(btc_schnorr_batch_verify(&items(&batch)), Ok(()));
+        }
+        assert_eq!(btc_schnorr_batch_verify(&[]), Err(VerifyError::InvalidMessage));
+    }
+
+    #[test]
+    fn batch_verify_pinpoints_failing_index() {
+        let mut batch = batch(12, 4);
+        batch[9].1[0] ^= 1;
+        assert_eq!(
+            btc_schnorr_batch_verify(&items(&batch)),
+            Err(VerifyError::at_index(9, VerifyError::BadSignature)),
+        );
+
+        let mut batch = self::batch(12, 4);
+        // swapping s values keeps the sum of s but breaks both equations
+        let (s3, s4) = (batch[3].2, batch[4].2);
+        batch[3].2[32..].copy_from_slice(&s4[32..]);
+        batch[4].2[32..].copy_from_slice(&s3[32..]);
+        assert_eq!(
+            btc_schnorr_batch_verify(&items(&batch)),
+            Err(VerifyError::at_index(3, VerifyError::BadSignature)),
+        );
+
+        let mut batch = self::batch(3, 1);
+        batch[2].2[..32].fill(0xff);
+        assert_eq!(
+            btc_schnorr_batch_verify(&items(&batch)),
+            Err(VerifyError::at_index(2, VerifyError::InvalidSignature)),
+        );
+    }
+
+    const SR_SECKEY: [u8; 32] = [0x11; 32];
+    const SR_ORDER: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141";
+
+    fn sr_pubkeys(seckey: &[u8; 32]) -> ([u8; 33], [u8; 65]) {
+        let pk = ECPK::from_secret_key(&ECSK::parse(seckey).unwrap());
+        (pk.serialize_compressed(), pk.serialize())
+    }
+
+    #[test]
+    fn sr_secp256k1_vector() {
+        // checked against an independent implementation of the scheme
+        let pubkey = hex::decode("034f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa").unwrap();
+        let signature = hex::decode(
+            "0a73bd217d56c510779c1b4bdb7f8f4719738ec66338161fd42f5d9e7591a182\
+             02d915170504fb7f18afcd03610d78b0a183cf976f3031faf6406e54e42c3ee2e9",
+        )
+        .unwrap();
+        assert_eq!(sr_pubkeys(&SR_SECKEY).0.to_vec(), pubkey);
+        assert_eq!(sr_secp256k1_sign(&SR_SECKEY, b"sr_secp256k1", &[0u8; 32]).unwrap().to_vec(), signature);
+        assert_eq!(sr_secp256k1_verify(&pubkey, b"sr_secp256k1", &signature), Ok(()));
+    }
+
+    #[test]
+    fn sr_secp256k1_accepts_both_pubkey_encodings() {
+        for i in 1..=16u8 {
+            let seckey = [i; 32];
+            let msg = [i; 40];
+            let (compressed, full) = sr_pubkeys(&seckey);
+            let signature = sr_secp256k1_sign(&seckey, &msg, &[i; 32]).unwrap();
+            assert_eq!(sr_secp256k1_verify(&compressed, &msg, &signature), Ok(()));
+            assert_eq!(sr_secp256k1_verify(&full, &msg, &signature), Ok(()));
+            assert_eq!(sr_secp256k1_verify(&compressed, &msg[1..], &signature), Err(VerifyError::BadSignature));
+        }
+    }
+
+    #[test]
+    fn sr_secp256k1_rejects_malformed_input() {
+        let (compressed, full) = sr_pubkeys(&SR_SECKEY);
+        let msg = b"sr_secp256k1";
+        let signature = sr_secp256k1_sign(&SR_SECKEY, msg, &[0u8; 32]).unwrap();
+
+        assert_eq!(
+            sr_secp256k1_verify(&full[1..], msg, &signature),
+            Err(VerifyError::InvalidPublicKeyLength { expected: 33, actual: 64 }),
+        );
+        assert_eq!(
+            sr_secp256k1_verify(&compressed, msg, &signature[..64]),
+            Err(VerifyError::InvalidSignatureLength { expected: 65, actual: 64 }),
+        );
+
+        // s outside [1, n)
+        let order = hex::decode(SR_ORDER).unwrap();
+        for s in [vec![0u8; 32], order, vec![0xff; 32]] {
+            let mut bad = signature;
+            bad[..32].copy_from_slice(&s);
+            assert_eq!(sr_secp256k1_verify(&compressed, msg, &bad), Err(VerifyError::InvalidSignature));
+        }
+
+        // R must be a compressed point on the curve, the identity has no such encoding
+        for prefix in [0x00, 0x04, 0x05] {
+            let mut bad = signature;
+            bad[32] = prefix;
+            assert_eq!(sr_secp256k1_verify(&compressed, msg, &bad), Err(VerifyError::InvalidSignature));
+        }
+        let mut bad = signature;
+        bad[32..].copy_from_slice(&[0u8; 33]);
+        assert_eq!(sr_secp256k1_verify(&compressed, msg, &bad), Err(VerifyError::InvalidSignature));
+
+        let mut bad_pubkey = compressed;
+        bad_pubkey[1..].fill(0xff);
+        assert_eq!(sr_secp256k1_verify(&bad_pubkey, msg, &signature), Err(VerifyError::InvalidPublicKey));
+        assert_eq!(sr_secp256k1_verify(&[0u8; 33], msg, &signature), Err(VerifyError::InvalidPublicKey));
+    }
+
+    #[test]
+    fn sr_secp256k1_sign_rejects_invalid_secret_keys() {
+        let order: [u8; 32] = hex::decode(SR_ORDER).unwrap().try_into().unwrap();
+        for seckey in [[0u8; 32], order] {
+            assert!(sr_secp256k1_sign(&seckey, b"msg", &[0u8; 32]).is_err());
+        }
+    }
+
+    fn pubkey33(hex: &str) -> [u8; 33] {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0327/vectors/key_agg_vectors.json
+    #[test]
+    fn musig_key_agg_vectors() {
+        let pubkeys = [
+            pubkey33("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            pubkey33("03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659"),
+            pubkey33("023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66"),
+        ];
+        for (indices, expected) in [
+            (&[0, 1, 2][..], "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"),
+            (&[2, 1, 0], "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B"),
+            (&[0, 0, 0], "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935"),
+            (&[0, 0, 1, 1], "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E"),
+        ] {
+            let keys: Vec<_> = indices.iter().map(|i| pubkeys[*i]).collect();
+            let ctx = musig_key_agg(&keys).unwrap();
+            assert_eq!(ctx.xonly_pubkey().to_vec(), hex::decode(expected).unwrap());
+        }
+
+        let mut invalid = pubkeys;
+        invalid[1][0] = 0x04;
+        assert_eq!(musig_key_agg(&invalid).unwrap_err(), VerifyError::at_index(1, VerifyError::InvalidPublicKey));
+        assert_eq!(musig_key_agg(&[]).unwrap_err(), VerifyError::InvalidPublicKey);
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0327/vectors/key_sort_vectors.json
+    #[test]
+    fn musig_key_sort_vector() {
+        let sorted = [
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "02DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EFF",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ]
+        .map(pubkey33);
+        let unsorted = [sorted[3], sorted[4], sorted[0], sorted[2], sorted[1]];
+        assert_eq!(musig_key_sort(&unsorted), sorted);
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+    #[test]
+    fn taproot_output_key_vector() {
+        let internal_key = hex::decode("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d").unwrap();
+        assert_eq!(
+            btc_taproot_output_key(&internal_key, None).unwrap().to_vec(),
+            hex::decode("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343").unwrap()
+        );
+        assert_eq!(btc_taproot_output_key(&[0xff; 32], None), Err(VerifyError::InvalidPublicKey));
+    }
+
+    struct MusigSigner {
+        seckey: Scalar,
+        pubkey: [u8; 33],
+        secnonce: (Scalar, Scalar),
+        pubnonce: [u8; 66],
+    }
+
+    fn musig_signer(i: u8) -> MusigSigner {
+        let scalar = |tag: &[u8]| ECSK::parse(&bip340_tagged_hash(tag, &[&[i]])).unwrap();
+        let point = |sk: &ECSK| ECPK::from_secret_key(sk).serialize_compressed();
+        let (seckey, k1, k2) = (scalar(b"seckey"), scalar(b"k1"), scalar(b"k2"));
+        let mut pubnonce = [0u8; 66];
+        pubnonce[..33].copy_from_slice(&point(&k1));
+        pubnonce[33..].copy_from_slice(&point(&k2));
+        MusigSigner { pubkey: point(&seckey), seckey: seckey.into(), secnonce: (k1.into(), k2.into()), pubnonce }
+    }
+
+    // BIP327 Sign
+    fn musig_sign(signer: &MusigSigner, ctx: &MusigKeyAggContext, aggnonce: &[u8; 66], msg: &[u8]) -> [u8; 32] {
+        let session = musig_session(ctx, aggnonce, msg).unwrap();
+        let (mut k1, mut k2) = signer.secnonce.clone();
+        if session.r[0] == 0x03 {
+            k1 = k1.neg();
+            k2 = k2.neg();
+        }
+        let g = if ctx.has_even_y() { Scalar::from_int(1) } else { Scalar::from_int(1).neg() };
+        let d = g * ctx.gacc.clone() * signer.seckey.clone();
+        (k1 + session.b * k2 + session.e * ctx.coefficient(&signer.pubkey) * d).b32()
+    }
+
+    fn musig_signing(count: u8, tweaks: &[([u8; 32], bool)], taproot: bool) {
+        let signers: Vec<_> = (0..count).map(musig_signer).collect();
+        let pubkeys = musig_key_sort(&signers.iter().map(|s| s.pubkey).collect::<Vec<_>>());
+        let mut ctx = musig_key_agg(&pubkeys).unwrap();
+        for (tweak, is_xonly) in tweaks {
+            ctx.apply_tweak(tweak, *is_xonly).unwrap();
+        }
+        if taproot {
+            let internal_key = ctx.xonly_pubkey();
+            ctx.apply_taproot_tweak(None).unwrap();
+            assert_eq!(ctx.xonly_pubkey(), btc_taproot_output_key(&internal_key, None).unwrap());
+        }
+
+        let msg = b"musig2 spend";
+        let aggnonce = musig_nonce_agg(&signers.iter().map(|s| s.pubnonce).collect::<Vec<_>>()).unwrap();
+        let mut psigs: Vec<_> = signers.iter().map(|s| musig_sign(s, &ctx, &aggnonce, msg)).collect();
+        for (signer, psig) in signers.iter().zip(&psigs) {
+            assert_eq!(musig_partial_sig_verify(&ctx, &aggnonce, msg, &signer.pubkey, &signer.pubnonce, psig), Ok(()));
+        }
+        let psig_refs: Vec<&[u8]> = psigs.iter().map(|p| &p[..]).collect();
+        let signature = musig_partial_sig_agg(&ctx, &aggnonce, msg, &psig_refs).unwrap();
+        assert_eq!(btc_schnorr_verify(&ctx.xonly_pubkey(), msg, &signature), Ok(()));
+
+        // a signer answering for another message is blamed, and breaks the final signature
+        psigs[0] = musig_sign(&signers[0], &ctx, &aggnonce, b"other");
+        assert_eq!(
+            musig_partial_sig_verify(&ctx, &aggnonce, msg, &signers[0].pubkey, &signers[0].pubnonce, &psigs[0]),
+            Err(VerifyError::BadSignature)
+        );
+        let psig_refs: Vec<&[u8]> = psigs.iter().map(|p| &p[..]).collect();
+        let signature = musig_partial_sig_agg(&ctx, &aggnonce, msg, &psig_refs).unwrap();
+        assert_eq!(btc_schnorr_verify(&ctx.xonly_pubkey(), msg, &signature), Err(VerifyError::BadSignature));
+    }
+
+    #[test]
+    fn musig_sign_and_aggregate() {
+        let plain = ([0x5a; 32], false);
+        let xonly = ([0x13; 32], true);
+        for count in [1, 2, 3, 5] {
+            musig_signing(count, &[], false);
+            musig_signing(count, &[], true);
+            musig_signing(count, &[plain, xonly], false);
+            musig_signing(count, &[xonly, plain], true);
+        }
+    }
+
+    #[test]
+    fn musig_rejects_malformed_contributions() {
+        let signers: Vec<_> = (0..3).map(musig_signer).collect();
+        let mut pubnonces: Vec<_> = signers.iter().map(|s| s.pubnonce).collect();
+        pubnonces[2][33] = 0x04;
+        assert_eq!(musig_nonce_agg(&pubnonces), Err(VerifyError::at_index(2, VerifyError::InvalidCommitment)));
+
+        let pubkeys: Vec<_> = signers.iter().map(|s| s.pubkey).collect();
+        let ctx = musig_key_agg(&pubkeys).unwrap();
+        let aggnonce = musig_nonce_agg(&signers.iter().map(|s| s.pubnonce).collect::<Vec<_>>()).unwrap();
+        let outsider = musig_signer(9);
+        let psig = musig_sign(&signers[0], &ctx, &aggnonce, b"msg");
+        assert_eq!(
+            musig_partial_sig_verify(&ctx, &aggnonce, b"msg", &outsider.pubkey, &signers[0].pubnonce, &psig),
+            Err(VerifyError::InvalidPublicKey)
+        );
+        let order = hex::decode("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141").unwrap();
+        assert_eq!(
+            musig_partial_sig_verify(&ctx, &aggnonce, b"msg", &signers[0].pubkey, &signers[0].pubnonce, &order),
+            Err(VerifyError::InvalidSignature)
+        );
+        assert_eq!(
+            musig_partial_sig_agg(&ctx, &aggnonce, b"msg", &[&psig, &order]),
+            Err(VerifyError::at_index(1, VerifyError::InvalidSignature))
+        );
+        let mut tweaked = ctx.clone();
+        assert_eq!(tweaked.apply_tweak(&order.try_into().unwrap(), true), Err(VerifyError::InvalidPublicKey));
     }
-    Ok(())
 }
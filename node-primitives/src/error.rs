@@ -15,10 +15,107 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use codec::{Decode, Encode, Input};
+use core::fmt;
 #[cfg(feature = "std")]
 pub use serde::{de::DeserializeOwned, Deserialize, Serialize};
-#[cfg(feature = "std")]
-use std::fmt;
+use sp_core::bounded::alloc::boxed::Box;
+
+/// Reason a signature verification failed.
+#[derive(Clone, Debug, PartialEq, Eq, Encode)]
+pub enum VerifyError {
+    /// Public key bytes are not a valid key for the scheme.
+    InvalidPublicKey,
+    InvalidPublicKeyLength { expected: u32, actual: u32 },
+    /// Signature bytes are malformed, e.g. a scalar out of range or a point off the curve.
+    InvalidSignature,
+    InvalidSignatureLength { expected: u32, actual: u32 },
+    /// Signed message can't be decoded.
+    InvalidMessage,
+    InvalidRecoveryId,
+    UnsupportedEngine,
+    /// Well formed signature that does not verify against the key and message.
+    BadSignature,
+    /// Failure of the signature at `index` of a multi-signature message.
+    AtIndex { index: u32, error: Box<VerifyError> },
+    /// High-S signature rejected by a strict low-S policy.
+    HighS,
+    /// Threshold signing commitment that is malformed or out of order.
+    InvalidCommitment,
+    /// Public key that is not among the signers the message requires.
+    NotRequiredSigner,
+}
+
+/// Deepest `AtIndex` nesting accepted when decoding a `VerifyError`.
+pub const MAX_VERIFY_ERROR_DEPTH: u32 = 8;
+
+impl VerifyError {
+    pub fn at_index(index: usize, error: VerifyError) -> Self {
+        VerifyError::AtIndex { index: index as u32, error: Box::new(error) }
+    }
+
+    fn decode_with_depth<I: Input>(input: &mut I, depth: u32) -> Result<Self, codec::Error> {
+        Ok(match input.read_byte()? {
+            0 => VerifyError::InvalidPublicKey,
+            1 => VerifyError::InvalidPublicKeyLength {
+                expected: u32::decode(input)?,
+                actual: u32::decode(input)?,
+            },
+            2 => VerifyError::InvalidSignature,
+            3 => VerifyError::InvalidSignatureLength {
+                expected: u32::decode(input)?,
+                actual: u32::decode(input)?,
+            },
+            4 => VerifyError::InvalidMessage,
+            5 => VerifyError::InvalidRecoveryId,
+            6 => VerifyError::UnsupportedEngine,
+            7 => VerifyError::BadSignature,
+            8 => {
+                if depth >= MAX_VERIFY_ERROR_DEPTH {
+                    return Err("VerifyError::AtIndex nested too deep".into());
+                }
+                let index = u32::decode(input)?;
+                let error = Self::decode_with_depth(input, depth + 1)?;
+                VerifyError::AtIndex { index, error: Box::new(error) }
+            }
+            9 => VerifyError::HighS,
+            10 => VerifyError::InvalidCommitment,
+            11 => VerifyError::NotRequiredSigner,
+            _ => return Err("invalid VerifyError variant".into()),
+        })
+    }
+}
+
+// Hand written so a run of `AtIndex` tags can't recurse without bound; variant
+// indices follow declaration order, matching the derived `Encode`.
+impl Decode for VerifyError {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        Self::decode_with_depth(input, 0)
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::InvalidPublicKey => write!(f, "invalid public key"),
+            VerifyError::InvalidPublicKeyLength { expected, actual } => {
+                write!(f, "invalid public key length {actual}, expect {expected}")
+            }
+            VerifyError::InvalidSignature => write!(f, "invalid signature"),
+            VerifyError::InvalidSignatureLength { expected, actual } => {
+                write!(f, "invalid signature length {actual}, expect {expected}")
+            }
+            VerifyError::InvalidMessage => write!(f, "invalid signed message"),
+            VerifyError::InvalidRecoveryId => write!(f, "invalid recovery id"),
+            VerifyError::UnsupportedEngine => write!(f, "unsupported signature engine"),
+            VerifyError::BadSignature => write!(f, "signature verify failed"),
+            VerifyError::AtIndex { index, error } => write!(f, "{error} for index: {index}"),
+            VerifyError::HighS => write!(f, "non-canonical high-S signature"),
+            VerifyError::InvalidCommitment => write!(f, "invalid signing commitment"),
+            VerifyError::NotRequiredSigner => write!(f, "public key is not a required signer"),
+        }
+    }
+}
 
 #[cfg(feature = "std")]
 #[derive(Debug, Serialize, Deserialize)]
@@ -200,3 +297,48 @@ impl CustomError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_error_round_trip() {
+        let errors = [
+            VerifyError::InvalidPublicKey,
+            VerifyError::InvalidPublicKeyLength { expected: 33, actual: 32 },
+            VerifyError::InvalidSignature,
+            VerifyError::InvalidSignatureLength { expected: 64, actual: 65 },
+            VerifyError::InvalidMessage,
+            VerifyError::InvalidRecoveryId,
+            VerifyError::UnsupportedEngine,
+            VerifyError::BadSignature,
+            VerifyError::at_index(3, VerifyError::at_index(1, VerifyError::HighS)),
+            VerifyError::HighS,
+            VerifyError::InvalidCommitment,
+            VerifyError::NotRequiredSigner,
+        ];
+        for error in errors {
+            let encoded = error.encode();
+            assert_eq!(VerifyError::decode(&mut &encoded[..]), Ok(error));
+        }
+        assert!(VerifyError::decode(&mut &[12u8][..]).is_err());
+    }
+
+    #[test]
+    fn verify_error_depth_is_capped() {
+        let mut error = VerifyError::BadSignature;
+        for i in 0..MAX_VERIFY_ERROR_DEPTH {
+            error = VerifyError::at_index(i as usize, error);
+        }
+        let encoded = error.encode();
+        assert_eq!(VerifyError::decode(&mut &encoded[..]), Ok(error.clone()));
+
+        let deeper = VerifyError::at_index(0, error).encode();
+        assert!(VerifyError::decode(&mut &deeper[..]).is_err());
+
+        // a long run of tags is rejected instead of overflowing the stack
+        let tags = [8u8, 0, 0, 0, 0].repeat(100_000);
+        assert!(VerifyError::decode(&mut &tags[..]).is_err());
+    }
+}